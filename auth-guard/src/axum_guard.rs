@@ -0,0 +1,197 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use tower::{Layer, Service};
+
+use crate::verify::Verifier;
+pub use crate::user::{Admin, AdminUser, AuthUser, RequireRole, Role};
+use crate::user::AuthFailure;
+
+/// Why a request was refused, sent to the client in the auth server's error envelope, `{"error": {"code": ..., "message": ...}}`
+#[derive(Debug)]
+pub enum AuthRejection {
+    /// 401, the request has no token or its token is not valid
+    Unauthorized(AuthFailure),
+    /// 403, the token does not grant the role the route requires
+    Forbidden,
+    /// 500, there is no AuthLayer and no Verifier extension to check the token with
+    NotConfigured
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: ErrorDetail
+}
+
+#[derive(Serialize)]
+struct ErrorDetail {
+    code: &'static str,
+    message: &'static str
+}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        let (status, code, message) = match self {
+            Self::Unauthorized(failure) => {
+                if let AuthFailure::Invalid(e) = failure {
+                    log::info!("Refused a token: {}", e);
+                }
+                (StatusCode::UNAUTHORIZED, "unauthorized", "A valid access token is required")
+            },
+            Self::Forbidden => (StatusCode::FORBIDDEN, "forbidden", "The request is not allowed"),
+            Self::NotConfigured => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "An internal server error has occurred")
+        };
+
+        (status, Json(ErrorBody { error: ErrorDetail { code, message } })).into_response()
+    }
+}
+
+fn authorization(headers: &HeaderMap) -> Option<&str> {
+    headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok())
+}
+
+/// A tower Layer that refuses requests without a valid JWT from the auth server in their `Authorization: Bearer` header with 401, for protecting a whole router:
+///
+/// ```no_run
+/// use auth_guard::axum_guard::{AuthLayer, AuthUser};
+/// use auth_guard::verify::Verifier;
+/// use axum::routing::get;
+/// use axum::Router;
+///
+/// async fn whoami(user: AuthUser) -> String {
+///     user.username
+/// }
+///
+/// let verifier = Verifier::from_env().expect("token verification is not configured");
+/// let app: Router = Router::new()
+///     .route("/whoami", get(whoami))
+///     .layer(AuthLayer::new(verifier));
+/// ```
+///
+/// The user is added to the request's extensions, where the AuthUser and RequireRole extractors read it without verifying the token again
+#[derive(Clone)]
+pub struct AuthLayer {
+    verifier: Arc<Verifier>
+}
+
+impl AuthLayer {
+    pub fn new(verifier: impl Into<Arc<Verifier>>) -> Self {
+        Self { verifier: verifier.into() }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService { inner, verifier: self.verifier.clone() }
+    }
+}
+
+/// The service an AuthLayer wraps around the routes
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    verifier: Arc<Verifier>
+}
+
+impl<S, B> Service<Request<B>> for AuthService<S>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        // The clone may not be ready, so the service that was polled handles this request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let verifier = self.verifier.clone();
+
+        Box::pin(async move {
+            match AuthUser::authenticate(&verifier, authorization(request.headers())).await {
+                Ok(user) => {
+                    request.extensions_mut().insert(user);
+                    inner.call(request).await
+                },
+                Err(failure) => Ok(AuthRejection::Unauthorized(failure).into_response())
+            }
+        })
+    }
+}
+
+/// Reads the user an AuthLayer authenticated. Without the layer, the token is verified with a Verifier added as an extension, which lets routes choose between requiring a user, `Option<AuthUser>` and no user at all:
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use auth_guard::axum_guard::AuthUser;
+/// use auth_guard::verify::Verifier;
+/// use axum::routing::get;
+/// use axum::{Extension, Router};
+///
+/// async fn greeting(user: Option<AuthUser>) -> String {
+///     user.map_or("Hello".to_string(), |user| format!("Hello {}", user.username))
+/// }
+///
+/// let verifier = Verifier::from_env().expect("token verification is not configured");
+/// let app: Router = Router::new()
+///     .route("/greeting", get(greeting))
+///     .layer(Extension(Arc::new(verifier)));
+/// ```
+///
+/// Requests with an invalid or expired token are refused with 401, as are requests without a token unless the user is optional
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+        let Some(verifier) = parts.extensions.get::<Arc<Verifier>>().cloned() else {
+            log::error!("AuthUser needs an AuthLayer or an Arc<Verifier> extension");
+            return Err(AuthRejection::NotConfigured);
+        };
+
+        AuthUser::authenticate(&verifier, authorization(&parts.headers)).await.map_err(AuthRejection::Unauthorized)
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for AuthUser {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        match <AuthUser as FromRequestParts<S>>::from_request_parts(parts, state).await {
+            Ok(user) => Ok(Some(user)),
+            Err(AuthRejection::Unauthorized(AuthFailure::MissingToken)) => Ok(None),
+            Err(rejection) => Err(rejection)
+        }
+    }
+}
+
+/// Refuses the request with 403 if the signed in user's token does not grant the role
+impl<S: Send + Sync, R: Role> FromRequestParts<S> for RequireRole<R> {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = <AuthUser as FromRequestParts<S>>::from_request_parts(parts, state).await?;
+
+        RequireRole::new(user).ok_or(AuthRejection::Forbidden)
+    }
+}
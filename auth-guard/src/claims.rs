@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The claims of a JWT issued by the auth server
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Claims {
+    pub user_id: i64,
+    pub username: String,
+    pub exp: i64,
+    /// The refresh session the token was issued for, missing from tokens issued before sessions were tracked
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+    /// A unique id for the token, used to revoke it before it expires; missing from tokens issued before revocation was supported
+    #[serde(default)]
+    pub jti: Option<Uuid>,
+    /// The user's roles when the token was issued, separated by spaces. Empty in tokens issued before roles were included
+    #[serde(default)]
+    pub scope: String
+}
+
+impl Claims {
+    /// The scopes the token grants, which are the roles of the user it was issued to
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_whitespace()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().any(|granted| granted == scope)
+    }
+}
+
+/// A JWT with a valid signature, split by whether it has expired so routes such as refresh can still read who it belonged to
+#[derive(Debug)]
+pub enum JwtStatus {
+    Valid(Claims),
+    Expired(Claims)
+}
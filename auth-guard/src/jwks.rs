@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey};
+use tokio::sync::RwLock;
+
+use crate::verify::{key_set, KeySet, VerifyError};
+
+/// How long fetched keys are used before the JWKS is fetched again
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// The shortest time between two fetches, so tokens with an unknown kid or an unreachable server cannot make every request fetch the JWKS
+const MIN_FETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a fetch may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The keys the auth server publishes at /.well-known/jwks.json, fetched when first needed and cached. The JWKS is fetched again once the keys are older than the TTL, or before the TTL when a token names a key that is not cached, as the server may have moved to a new key. Fetches are at least 10 seconds apart, and if a fetch fails the keys already cached keep being used
+pub struct JwksCache {
+    url: String,
+    ttl: Duration,
+    client: reqwest::Client,
+    cached: RwLock<Cached>
+}
+
+#[derive(Default)]
+struct Cached {
+    keys: KeySet,
+    /// When the keys were last fetched successfully
+    fetched_at: Option<Instant>,
+    /// When a fetch was last tried, whether or not it succeeded
+    attempted_at: Option<Instant>
+}
+
+impl Cached {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        self.fetched_at.is_some_and(|fetched_at| fetched_at.elapsed() < ttl)
+    }
+
+    fn may_fetch(&self) -> bool {
+        self.attempted_at.is_none_or(|attempted_at| attempted_at.elapsed() >= MIN_FETCH_INTERVAL)
+    }
+}
+
+impl JwksCache {
+    /// # Arguments
+    /// - `url`: The JWKS address, such as https://auth.example.com/.well-known/jwks.json
+    /// - `ttl`: How long fetched keys are used before fetching them again
+    ///
+    /// # Returns
+    /// The cache, which has not fetched anything yet, or VerifyError::Fetch if the HTTP client cannot be created
+    pub fn new(url: &str, ttl: Duration) -> Result<Self, VerifyError> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(VerifyError::Fetch)?;
+
+        Ok(Self { url: url.to_string(), ttl, client, cached: RwLock::new(Cached::default()) })
+    }
+
+    /// Finds the key with a kid, fetching the JWKS if the cached keys are stale or do not include it
+    ///
+    /// # Arguments
+    /// - `kid`: The kid from the token's header
+    ///
+    /// # Returns
+    /// The key and the algorithm it signs with, or a VerifyError if there is no such key
+    pub async fn key(&self, kid: &str) -> Result<(DecodingKey, Algorithm), VerifyError> {
+        {
+            let cached = self.cached.read().await;
+            match cached.keys.get(kid) {
+                Some(key) if cached.is_fresh(self.ttl) => return Ok(key.clone()),
+                None if !cached.may_fetch() => return Err(VerifyError::UnknownKey(kid.to_string())),
+                _ => {}
+            }
+        }
+
+        let mut cached = self.cached.write().await;
+        // Another request may have fetched the keys while this one waited for the lock
+        let needs_fetch = !cached.is_fresh(self.ttl) || !cached.keys.contains_key(kid);
+        if needs_fetch && cached.may_fetch() {
+            cached.attempted_at = Some(Instant::now());
+            match self.fetch().await {
+                Ok(keys) => {
+                    cached.keys = keys;
+                    cached.fetched_at = cached.attempted_at;
+                },
+                Err(e) if cached.keys.is_empty() => return Err(e),
+                Err(e) => log::warn!("{}, using the cached keys", e)
+            }
+        }
+
+        cached.keys.get(kid).cloned().ok_or_else(|| VerifyError::UnknownKey(kid.to_string()))
+    }
+
+    async fn fetch(&self) -> Result<KeySet, VerifyError> {
+        let jwks = self.client.get(&self.url)
+            .send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(VerifyError::Fetch)?
+            .json::<JwkSet>().await
+            .map_err(VerifyError::Fetch)?;
+
+        Ok(key_set(&jwks))
+    }
+}
@@ -0,0 +1,10 @@
+//! Validates the JWTs issued by the Rocket authentication server, for other services that accept them. Tokens are checked against the server's shared secret, a public key, or the keys the server publishes at /.well-known/jwks.json. Guards for Rocket and Axum authenticate requests with the same verification
+pub mod claims;
+pub mod user;
+pub mod verify;
+#[cfg(feature = "jwks")]
+pub mod jwks;
+#[cfg(feature = "rocket")]
+pub mod rocket_guard;
+#[cfg(feature = "axum")]
+pub mod axum_guard;
@@ -0,0 +1,67 @@
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+use crate::verify::Verifier;
+pub use crate::user::{Admin, AdminUser, AuthUser, RequireRole, Role};
+use crate::user::AuthFailure;
+
+/// Authenticates a request carrying a valid JWT from the auth server in its `Authorization: Bearer` header. The Verifier must be managed by rocket:
+///
+/// ```no_run
+/// # use auth_guard::verify::Verifier;
+/// let rocket = rocket::build().manage(Verifier::from_env().expect("token verification is not configured"));
+/// ```
+///
+/// Requests without a token are forwarded with a 401 status, and requests with an invalid or expired token fail with 401
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthUser {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(verifier) = request.rocket().state::<Verifier>() else {
+            rocket::error!("AuthUser needs a Verifier managed by rocket");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        match AuthUser::authenticate(verifier, request.headers().get_one("Authorization")).await {
+            Ok(user) => Outcome::Success(user),
+            Err(AuthFailure::MissingToken) => Outcome::Forward(Status::Unauthorized),
+            Err(AuthFailure::Expired) => Outcome::Error((Status::Unauthorized, ())),
+            Err(e) => {
+                rocket::info!("Refused a token: {}", e);
+                Outcome::Error((Status::Unauthorized, ()))
+            }
+        }
+    }
+}
+
+/// Fails with 403 if the signed in user's token does not grant the role:
+///
+/// ```
+/// use auth_guard::rocket_guard::{RequireRole, Role};
+///
+/// pub struct Billing;
+///
+/// impl Role for Billing {
+///     const NAME: &'static str = "billing";
+/// }
+///
+/// #[rocket::get("/invoices")]
+/// fn invoices(user: RequireRole<Billing>) -> String {
+///     format!("Invoices for {}", user.user.username)
+/// }
+/// ```
+#[rocket::async_trait]
+impl<'r, R: Role> FromRequest<'r> for RequireRole<R> {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = rocket::outcome::try_outcome!(request.guard::<AuthUser>().await);
+
+        match RequireRole::new(user) {
+            Some(user) => Outcome::Success(user),
+            None => Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
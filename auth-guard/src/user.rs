@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use uuid::Uuid;
+
+use crate::claims::{Claims, JwtStatus};
+use crate::verify::{bearer_token, Verifier, VerifyError};
+
+/// A user signed in with a valid JWT from the auth server. The Rocket and Axum guards both authenticate requests as this
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: i64,
+    pub username: String,
+    pub session_id: Option<Uuid>,
+    pub claims: Claims
+}
+
+/// Why a request could not be authenticated
+#[derive(Debug)]
+pub enum AuthFailure {
+    /// There is no `Authorization: Bearer` header
+    MissingToken,
+    /// The token's signature is valid but it has expired
+    Expired,
+    /// The token is malformed, signed with another key, or its key could not be found
+    Invalid(VerifyError)
+}
+
+impl std::fmt::Display for AuthFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingToken => write!(f, "no bearer token"),
+            Self::Expired => write!(f, "the token has expired"),
+            Self::Invalid(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for AuthFailure {}
+
+impl AuthUser {
+    /// Verifies the bearer token of a request
+    ///
+    /// # Arguments
+    /// - `verifier`: Checks the token's signature
+    /// - `authorization`: The request's Authorization header, if it has one
+    ///
+    /// # Returns
+    /// The user the token was issued to, or an AuthFailure if there is no token or it is not valid
+    pub async fn authenticate(verifier: &Verifier, authorization: Option<&str>) -> Result<Self, AuthFailure> {
+        let token = authorization.and_then(bearer_token).ok_or(AuthFailure::MissingToken)?;
+
+        match verifier.verify(token).await {
+            Ok(JwtStatus::Valid(claims)) => Ok(Self::from(claims)),
+            Ok(JwtStatus::Expired(_)) => Err(AuthFailure::Expired),
+            Err(e) => Err(AuthFailure::Invalid(e))
+        }
+    }
+
+    /// Whether the user had a role when the token was issued
+    pub fn has_role(&self, role: &str) -> bool {
+        self.claims.has_scope(role)
+    }
+}
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        Self {
+            user_id: claims.user_id,
+            username: claims.username.clone(),
+            session_id: claims.session_id,
+            claims
+        }
+    }
+}
+
+/// A role a route requires, for use with RequireRole:
+///
+/// ```
+/// use auth_guard::user::{RequireRole, Role};
+///
+/// pub struct Billing;
+///
+/// impl Role for Billing {
+///     const NAME: &'static str = "billing";
+/// }
+///
+/// fn invoices(user: RequireRole<Billing>) -> String {
+///     format!("Invoices for {}", user.user.username)
+/// }
+/// ```
+pub trait Role: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// The admin role, which the auth server grants access to its /admin routes
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// A signed in user whose token grants a role, as one of the space separated scopes in its scope claim. The guards refuse requests with 403 if it does not. Roles are read from the token, so a change to a user's roles applies once their next token is issued
+pub struct RequireRole<R: Role> {
+    pub user: AuthUser,
+    role: PhantomData<R>
+}
+
+impl<R: Role> RequireRole<R> {
+    /// # Returns
+    /// The user if their token grants the role, or None if it does not
+    pub fn new(user: AuthUser) -> Option<Self> {
+        user.has_role(R::NAME).then_some(Self { user, role: PhantomData })
+    }
+}
+
+/// A signed in user with the admin role
+pub type AdminUser = RequireRole<Admin>;
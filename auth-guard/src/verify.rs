@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::env;
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+
+use crate::claims::{Claims, JwtStatus};
+#[cfg(feature = "jwks")]
+use crate::jwks::JwksCache;
+
+/// Keys by their key id, with the algorithm each one signs with
+pub type KeySet = HashMap<String, (DecodingKey, Algorithm)>;
+
+/// Errors from verifying a JWT
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The token is malformed or its signature does not match
+    Token(jsonwebtoken::errors::Error),
+    /// The token has no kid header, so its key cannot be found in a JWKS
+    MissingKeyId,
+    /// No key with the token's kid is known, even after fetching the JWKS again
+    UnknownKey(String),
+    /// The JWKS could not be fetched
+    #[cfg(feature = "jwks")]
+    Fetch(reqwest::Error),
+    /// The verifier is not configured correctly
+    Config(String)
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Token(e) => write!(f, "invalid token: {}", e),
+            Self::MissingKeyId => write!(f, "the token has no key id"),
+            Self::UnknownKey(kid) => write!(f, "no key with id {}", kid),
+            #[cfg(feature = "jwks")]
+            Self::Fetch(e) => write!(f, "failed to fetch the JWKS: {}", e),
+            Self::Config(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<jsonwebtoken::errors::Error> for VerifyError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        Self::Token(e)
+    }
+}
+
+/// Decodes a JWT and checks its signature. Expiry is checked here rather than by jsonwebtoken so an expired token can still be read; this is the check the auth server itself uses
+///
+/// # Arguments
+/// - `token`: The JWT
+/// - `key`: The key the JWT must be signed with
+/// - `algorithm`: The algorithm the key signs with; tokens claiming any other algorithm are refused
+///
+/// # Returns
+/// The claims, marked valid or expired, or a jsonwebtoken error if the token is malformed or its signature does not match
+pub fn decode_with_key(token: &str, key: &DecodingKey, algorithm: Algorithm) -> Result<JwtStatus, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::new(algorithm);
+    validation.validate_exp = false;
+
+    let token_data = decode::<Claims>(token, key, &validation)?;
+
+    if token_data.claims.exp <= chrono::Utc::now().timestamp() {
+        Ok(JwtStatus::Expired(token_data.claims))
+    } else {
+        Ok(JwtStatus::Valid(token_data.claims))
+    }
+}
+
+/// Reads the keys of a JWKS, skipping keys without a kid or alg and keys of unsupported types
+///
+/// # Arguments
+/// - `jwks`: The key set, as published at /.well-known/jwks.json
+///
+/// # Returns
+/// The usable keys by their kid
+pub fn key_set(jwks: &JwkSet) -> KeySet {
+    jwks.keys.iter()
+        .filter_map(|jwk| {
+            let kid = jwk.common.key_id.clone()?;
+            let algorithm = jwk.common.key_algorithm?.to_string().parse::<Algorithm>().ok()?;
+            let key = DecodingKey::from_jwk(jwk).ok()?;
+            Some((kid, (key, algorithm)))
+        })
+        .collect()
+}
+
+/// Reads the token from an `Authorization: Bearer` header value
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.split_once(' ')?;
+    match scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() {
+        true => Some(token.trim()),
+        false => None
+    }
+}
+
+/// Where a Verifier finds the key a token was signed with
+enum Keys {
+    Static(DecodingKey, Algorithm),
+    Set(KeySet),
+    #[cfg(feature = "jwks")]
+    Jwks(JwksCache)
+}
+
+/// Verifies JWTs issued by the auth server. Verification only checks the signature and expiry: unlike the server's own AuthUser guard it does not see accounts that were disabled or tokens that were revoked, which only the server's /introspect route reports
+pub struct Verifier {
+    keys: Keys
+}
+
+impl Verifier {
+    /// Verifies HS256 tokens with the secret the server signs with, its JWT_PRIVATE_KEY
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self { keys: Keys::Static(DecodingKey::from_secret(secret), Algorithm::HS256) }
+    }
+
+    /// Verifies RS256 or ES256 tokens with the server's public key in PEM format
+    ///
+    /// # Arguments
+    /// - `algorithm`: The server's JWT_ALGORITHM
+    /// - `pem`: The public key
+    ///
+    /// # Returns
+    /// The verifier, or an error if the key cannot be read or the algorithm is not RS256 or ES256
+    pub fn from_public_key_pem(algorithm: Algorithm, pem: &[u8]) -> Result<Self, VerifyError> {
+        let key = match algorithm {
+            Algorithm::RS256 => DecodingKey::from_rsa_pem(pem)?,
+            Algorithm::ES256 => DecodingKey::from_ec_pem(pem)?,
+            _ => return Err(VerifyError::Config(format!("{:?} is not a public key algorithm, use RS256 or ES256", algorithm)))
+        };
+
+        Ok(Self { keys: Keys::Static(key, algorithm) })
+    }
+
+    /// Verifies tokens with a fixed set of keys, found by the kid of each token
+    pub fn from_jwk_set(jwks: &JwkSet) -> Self {
+        Self { keys: Keys::Set(key_set(jwks)) }
+    }
+
+    /// Verifies tokens with the keys published by the server, fetched when first needed and cached
+    ///
+    /// # Arguments
+    /// - `cache`: The JWKS cache, see JwksCache::new
+    #[cfg(feature = "jwks")]
+    pub fn from_jwks(cache: JwksCache) -> Self {
+        Self { keys: Keys::Jwks(cache) }
+    }
+
+    /// Creates a verifier from the environment: AUTH_JWKS_URL (with AUTH_JWKS_CACHE_TTL in seconds, default 300) when the server signs with a public key algorithm, or AUTH_JWT_SECRET holding the server's JWT_PRIVATE_KEY for HS256
+    ///
+    /// # Returns
+    /// The verifier, or VerifyError::Config if neither variable is set or one is invalid
+    pub fn from_env() -> Result<Self, VerifyError> {
+        #[cfg(feature = "jwks")]
+        if let Ok(url) = env::var("AUTH_JWKS_URL") {
+            let ttl = match env::var("AUTH_JWKS_CACHE_TTL").map(|ttl| ttl.parse::<u64>()) {
+                Ok(Ok(ttl)) => std::time::Duration::from_secs(ttl),
+                Ok(Err(_)) => return Err(VerifyError::Config("AUTH_JWKS_CACHE_TTL is not a number of seconds".to_string())),
+                Err(_) => crate::jwks::DEFAULT_CACHE_TTL
+            };
+            return Ok(Self::from_jwks(JwksCache::new(&url, ttl)?));
+        }
+
+        match env::var("AUTH_JWT_SECRET") {
+            Ok(secret) if !secret.is_empty() => Ok(Self::from_secret(secret.as_bytes())),
+            _ => Err(VerifyError::Config("AUTH_JWKS_URL or AUTH_JWT_SECRET must be set".to_string()))
+        }
+    }
+
+    /// Verifies a JWT's signature and reads its claims
+    ///
+    /// # Arguments
+    /// - `token`: The JWT
+    ///
+    /// # Returns
+    /// The claims, marked valid or expired, or a VerifyError if the token cannot be trusted
+    pub async fn verify(&self, token: &str) -> Result<JwtStatus, VerifyError> {
+        match &self.keys {
+            Keys::Static(key, algorithm) => Ok(decode_with_key(token, key, *algorithm)?),
+            Keys::Set(keys) => {
+                let kid = key_id(token)?;
+                let (key, algorithm) = keys.get(&kid).ok_or(VerifyError::UnknownKey(kid))?;
+                Ok(decode_with_key(token, key, *algorithm)?)
+            },
+            #[cfg(feature = "jwks")]
+            Keys::Jwks(cache) => {
+                let (key, algorithm) = cache.key(&key_id(token)?).await?;
+                Ok(decode_with_key(token, &key, algorithm)?)
+            }
+        }
+    }
+}
+
+/// Reads the kid from a token's header without checking the token
+fn key_id(token: &str) -> Result<String, VerifyError> {
+    decode_header(token)?.kid.ok_or(VerifyError::MissingKeyId)
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use auth_guard::axum_guard::{AdminUser, AuthLayer, AuthUser};
+use auth_guard::claims::Claims;
+use auth_guard::verify::Verifier;
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use tower::ServiceExt;
+
+const SECRET: &[u8] = b"shared-secret";
+
+fn token(secret: &[u8], scope: &str, exp: i64) -> String {
+    let claims = Claims {
+        user_id: 3,
+        username: "carol".to_string(),
+        exp,
+        session_id: None,
+        jti: None,
+        scope: scope.to_string()
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret)).unwrap()
+}
+
+fn valid_token(scope: &str) -> String {
+    token(SECRET, scope, chrono::Utc::now().timestamp() + 60)
+}
+
+async fn whoami(user: AuthUser) -> String {
+    format!("{} {}", user.user_id, user.username)
+}
+
+async fn greeting(user: Option<AuthUser>) -> String {
+    user.map_or("Hello".to_string(), |user| format!("Hello {}", user.username))
+}
+
+async fn reports(admin: AdminUser) -> String {
+    format!("Reports for {}", admin.user.username)
+}
+
+/// Sends a GET request, returning the status and body
+async fn get_route(app: Router, uri: &str, token: Option<&str>) -> (StatusCode, String) {
+    let mut request = Request::get(uri);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn the_layer_refuses_requests_without_a_valid_token() {
+    let app = Router::new()
+        .route("/whoami", get(whoami))
+        .layer(AuthLayer::new(Verifier::from_secret(SECRET)));
+
+    assert_eq!(get_route(app.clone(), "/whoami", Some(&valid_token(""))).await, (StatusCode::OK, "3 carol".to_string()));
+
+    let (status, body) = get_route(app.clone(), "/whoami", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let now = chrono::Utc::now().timestamp();
+    assert_eq!(get_route(app.clone(), "/whoami", Some(&token(SECRET, "", now - 60))).await.0, StatusCode::UNAUTHORIZED);
+    let forged = token(b"another-secret", "", now + 60);
+    assert_eq!(get_route(app, "/whoami", Some(&forged)).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn extractors_verify_tokens_with_a_verifier_extension() {
+    let app = Router::new()
+        .route("/whoami", get(whoami))
+        .route("/greeting", get(greeting))
+        .layer(Extension(Arc::new(Verifier::from_secret(SECRET))));
+
+    assert_eq!(get_route(app.clone(), "/whoami", Some(&valid_token(""))).await.0, StatusCode::OK);
+    assert_eq!(get_route(app.clone(), "/whoami", None).await.0, StatusCode::UNAUTHORIZED);
+
+    // An optional user is None without a token, but an invalid token is still refused
+    assert_eq!(get_route(app.clone(), "/greeting", None).await, (StatusCode::OK, "Hello".to_string()));
+    assert_eq!(get_route(app.clone(), "/greeting", Some(&valid_token(""))).await, (StatusCode::OK, "Hello carol".to_string()));
+    assert_eq!(get_route(app, "/greeting", Some("not.a.token")).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn roles_are_read_from_the_scope_claim() {
+    let app = Router::new()
+        .route("/reports", get(reports))
+        .layer(AuthLayer::new(Verifier::from_secret(SECRET)));
+
+    let (status, body) = get_route(app.clone(), "/reports", Some(&valid_token("support"))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "forbidden");
+
+    assert_eq!(get_route(app, "/reports", Some(&valid_token("support admin"))).await, (StatusCode::OK, "Reports for carol".to_string()));
+}
+
+#[tokio::test]
+async fn extractors_without_a_verifier_are_an_internal_error() {
+    let app = Router::new().route("/whoami", get(whoami));
+
+    assert_eq!(get_route(app, "/whoami", Some(&valid_token(""))).await.0, StatusCode::INTERNAL_SERVER_ERROR);
+}
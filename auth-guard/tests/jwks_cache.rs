@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use auth_guard::claims::{Claims, JwtStatus};
+use auth_guard::jwks::JwksCache;
+use auth_guard::verify::{Verifier, VerifyError};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const PRIVATE_KEY: &[u8] = include_bytes!("keys/es256.pem");
+const KID: &str = "test-key";
+
+fn signing_key() -> EncodingKey {
+    EncodingKey::from_ec_pem(PRIVATE_KEY).unwrap()
+}
+
+fn token(kid: &str) -> String {
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(kid.to_string());
+    let claims = Claims {
+        user_id: 7,
+        username: "downstream".to_string(),
+        exp: chrono::Utc::now().timestamp() + 60,
+        session_id: None,
+        jti: None,
+        scope: "admin support".to_string()
+    };
+
+    encode(&header, &claims, &signing_key()).unwrap()
+}
+
+/// Serves the public half of the test key as a JWKS, counting the requests
+async fn jwks_server() -> (String, Arc<AtomicUsize>) {
+    let mut jwk = Jwk::from_encoding_key(&signing_key(), Algorithm::ES256).unwrap();
+    jwk.common.key_id = Some(KID.to_string());
+    let body = serde_json::to_string(&JwkSet { keys: vec![jwk] }).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/.well-known/jwks.json", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            counter.fetch_add(1, Ordering::SeqCst);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (url, requests)
+}
+
+#[tokio::test]
+async fn keys_are_fetched_once_and_cached() {
+    let (url, requests) = jwks_server().await;
+    let verifier = Verifier::from_jwks(JwksCache::new(&url, Duration::from_secs(60)).unwrap());
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+    for _ in 0..3 {
+        match verifier.verify(&token(KID)).await.unwrap() {
+            JwtStatus::Valid(claims) => {
+                assert_eq!(claims.username, "downstream");
+                assert!(claims.has_scope("support"));
+            },
+            JwtStatus::Expired(_) => panic!("the token should not have expired")
+        }
+    }
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn unknown_keys_do_not_fetch_on_every_request() {
+    let (url, requests) = jwks_server().await;
+    let verifier = Verifier::from_jwks(JwksCache::new(&url, Duration::from_secs(60)).unwrap());
+
+    assert!(matches!(verifier.verify(&token("rotated-key")).await, Err(VerifyError::UnknownKey(_))));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // The keys were just fetched, so tokens naming a key the server does not have are refused without fetching again
+    assert!(matches!(verifier.verify(&token("another-key")).await, Err(VerifyError::UnknownKey(_))));
+    verifier.verify(&token(KID)).await.unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn stale_keys_are_fetched_at_most_once_per_interval() {
+    let (url, requests) = jwks_server().await;
+    let verifier = Verifier::from_jwks(JwksCache::new(&url, Duration::ZERO).unwrap());
+
+    verifier.verify(&token(KID)).await.unwrap();
+    verifier.verify(&token(KID)).await.unwrap();
+    // Fetches are spaced out even when the keys are always stale
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn unreachable_jwks_is_an_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/.well-known/jwks.json", listener.local_addr().unwrap());
+    drop(listener);
+
+    let verifier = Verifier::from_jwks(JwksCache::new(&url, Duration::from_secs(60)).unwrap());
+    assert!(matches!(verifier.verify(&token(KID)).await, Err(VerifyError::Fetch(_))));
+}
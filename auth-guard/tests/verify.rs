@@ -0,0 +1,57 @@
+use auth_guard::claims::{Claims, JwtStatus};
+use auth_guard::verify::{bearer_token, Verifier};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+const RSA_PRIVATE_KEY: &[u8] = include_bytes!("keys/rs256.pem");
+const RSA_PUBLIC_KEY: &[u8] = include_bytes!("keys/rs256.pub.pem");
+const SECRET: &[u8] = b"shared-secret";
+
+fn claims(exp: i64) -> Claims {
+    Claims {
+        user_id: 1,
+        username: "alice".to_string(),
+        exp,
+        session_id: None,
+        jti: None,
+        scope: String::new()
+    }
+}
+
+fn rs256_token(exp: i64) -> String {
+    encode(&Header::new(Algorithm::RS256), &claims(exp), &EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn public_keys_verify_tokens_and_report_expiry() {
+    let verifier = Verifier::from_public_key_pem(Algorithm::RS256, RSA_PUBLIC_KEY).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    assert!(matches!(verifier.verify(&rs256_token(now + 60)).await, Ok(JwtStatus::Valid(_))));
+    match verifier.verify(&rs256_token(now - 60)).await {
+        Ok(JwtStatus::Expired(claims)) => assert_eq!(claims.username, "alice"),
+        other => panic!("expected an expired token, got {:?}", other)
+    }
+}
+
+#[tokio::test]
+async fn tokens_with_another_algorithm_are_refused() {
+    let verifier = Verifier::from_public_key_pem(Algorithm::RS256, RSA_PUBLIC_KEY).unwrap();
+    let exp = chrono::Utc::now().timestamp() + 60;
+
+    // An HS256 token signed with the public key as its secret must not pass as RS256
+    let forged = encode(&Header::new(Algorithm::HS256), &claims(exp), &EncodingKey::from_secret(RSA_PUBLIC_KEY)).unwrap();
+    assert!(verifier.verify(&forged).await.is_err());
+
+    let hs256 = encode(&Header::new(Algorithm::HS256), &claims(exp), &EncodingKey::from_secret(SECRET)).unwrap();
+    assert!(matches!(Verifier::from_secret(SECRET).verify(&hs256).await, Ok(JwtStatus::Valid(_))));
+    assert!(Verifier::from_secret(b"another-secret").verify(&hs256).await.is_err());
+}
+
+#[test]
+fn bearer_tokens_are_read_from_the_header() {
+    assert_eq!(bearer_token("Bearer abc.def.ghi"), Some("abc.def.ghi"));
+    assert_eq!(bearer_token("bearer abc"), Some("abc"));
+    assert_eq!(bearer_token("Basic abc"), None);
+    assert_eq!(bearer_token("Bearer "), None);
+    assert_eq!(bearer_token("abc"), None);
+}
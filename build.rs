@@ -0,0 +1,4 @@
+// Rebuilds when migrations are added so sqlx::migrate! embeds them
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,20 @@
+-- The schema previously created by hand with database_setup.sql. IF NOT EXISTS lets databases set up that way adopt the migrations
+CREATE TABLE IF NOT EXISTS users (
+  user_id SERIAL UNIQUE PRIMARY KEY NOT NULL,
+  username varchar(255) UNIQUE NOT NULL,
+  password varchar(255) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+  token_id SERIAL UNIQUE PRIMARY KEY NOT NULL,
+  user_id integer NOT NULL REFERENCES users (user_id),
+  session_id uuid NOT NULL,
+  token_hash varchar(64) NOT NULL,
+  created_at timestamptz NOT NULL DEFAULT now(),
+  expires_at timestamptz NOT NULL,
+  rotated_at timestamptz,
+  revoked_at timestamptz
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS refresh_tokens_token_hash_idx ON refresh_tokens (token_hash);
+CREATE INDEX IF NOT EXISTS refresh_tokens_session_id_idx ON refresh_tokens (session_id);
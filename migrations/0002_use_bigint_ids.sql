@@ -0,0 +1,8 @@
+-- The server reads ids as i64, so SERIAL integer columns are widened to bigint. This is a no-op where SERIAL is already 64 bit, as on CockroachDB
+ALTER TABLE users ALTER COLUMN user_id TYPE bigint;
+ALTER SEQUENCE IF EXISTS users_user_id_seq AS bigint;
+
+ALTER TABLE refresh_tokens ALTER COLUMN token_id TYPE bigint;
+ALTER SEQUENCE IF EXISTS refresh_tokens_token_id_seq AS bigint;
+
+ALTER TABLE refresh_tokens ALTER COLUMN user_id TYPE bigint;
@@ -0,0 +1,7 @@
+ALTER TABLE users ADD COLUMN disabled_at timestamptz;
+
+CREATE TABLE user_roles (
+  user_id bigint NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+  role varchar(64) NOT NULL,
+  PRIMARY KEY (user_id, role)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE users ADD COLUMN display_name varchar(255);
+ALTER TABLE users ADD COLUMN email varchar(255);
+ALTER TABLE users ADD COLUMN email_verified_at timestamptz;
+ALTER TABLE users ADD COLUMN email_token_hash varchar(64);
+ALTER TABLE users ADD COLUMN email_token_expires_at timestamptz;
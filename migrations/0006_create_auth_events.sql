@@ -0,0 +1,10 @@
+CREATE TABLE auth_events (
+  event_id bigserial PRIMARY KEY,
+  user_id bigint REFERENCES users (user_id) ON DELETE CASCADE,
+  event_type varchar(64) NOT NULL,
+  ip_address varchar(45),
+  user_agent varchar(512),
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX auth_events_user_id_idx ON auth_events (user_id, created_at);
@@ -0,0 +1,6 @@
+CREATE TABLE revoked_access_tokens (
+  jti uuid PRIMARY KEY,
+  expires_at timestamptz NOT NULL
+);
+
+CREATE INDEX revoked_access_tokens_expires_at_idx ON revoked_access_tokens (expires_at);
@@ -0,0 +1,7 @@
+ALTER TABLE users ADD COLUMN disabled_at TEXT;
+
+CREATE TABLE user_roles (
+  user_id INTEGER NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+  role TEXT NOT NULL,
+  PRIMARY KEY (user_id, role)
+);
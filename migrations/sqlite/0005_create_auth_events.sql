@@ -0,0 +1,10 @@
+CREATE TABLE auth_events (
+  event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER REFERENCES users (user_id) ON DELETE CASCADE,
+  event_type TEXT NOT NULL,
+  ip_address TEXT,
+  user_agent TEXT,
+  created_at TEXT NOT NULL
+);
+
+CREATE INDEX auth_events_user_id_idx ON auth_events (user_id, created_at);
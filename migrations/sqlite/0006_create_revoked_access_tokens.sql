@@ -0,0 +1,6 @@
+CREATE TABLE revoked_access_tokens (
+  jti BLOB PRIMARY KEY,
+  expires_at TEXT NOT NULL
+);
+
+CREATE INDEX revoked_access_tokens_expires_at_idx ON revoked_access_tokens (expires_at);
@@ -0,0 +1,211 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use dotenv::dotenv;
+use rustls::crypto::CryptoProvider;
+use uuid::Uuid;
+
+use server::cli::admin;
+use server::db::connect::connect_stores;
+use server::db::store::Stores;
+
+/// Manages users and sessions in the database the server uses, picked with STORAGE_BACKEND as for the server
+#[derive(Parser)]
+#[command(name = "admin")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create, delete, disable and reset the password of users
+    #[command(subcommand)]
+    Users(UserCommand),
+    /// List and revoke refresh sessions
+    #[command(subcommand)]
+    Sessions(SessionCommand),
+    /// Assign roles to users
+    #[command(subcommand)]
+    Roles(RoleCommand),
+    /// Generate new signing keys
+    #[command(subcommand)]
+    Keys(KeyCommand),
+    /// Apply pending migrations
+    Migrate {
+        /// Only check that the schema is up to date
+        #[arg(long)]
+        check: bool
+    }
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Create a user, prompting for the password
+    Create { username: String },
+    /// Delete a user with their sessions and roles straight away
+    Delete { username: String },
+    /// Remove the users deleted through the API whose grace period has passed
+    Purge {
+        /// Also remove the users still in their grace period
+        #[arg(long)]
+        all: bool
+    },
+    /// Stop a user from signing in and revoke their sessions
+    Disable { username: String },
+    /// Allow a disabled user to sign in again
+    Enable { username: String },
+    /// Set a new password, prompting for it, and revoke the user's sessions
+    ResetPassword { username: String }
+}
+
+#[derive(Subcommand)]
+enum SessionCommand {
+    /// List the active sessions of a user
+    List { username: String },
+    /// Revoke a single session
+    Revoke { session_id: Uuid },
+    /// Revoke every session of a user
+    RevokeAll { username: String }
+}
+
+#[derive(Subcommand)]
+enum RoleCommand {
+    /// List the roles of a user
+    List { username: String },
+    /// Assign a role to a user
+    Add { username: String, role: String },
+    /// Remove a role from a user
+    Remove { username: String, role: String }
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+    /// Print a new key to put in .env
+    Rotate { key: SigningKey },
+    /// Revoke every session, once the server has restarted with a rotated key and they can no longer be refreshed
+    RevokeSessions
+}
+
+/// The secrets the server signs tokens with
+#[derive(Clone, Copy, ValueEnum)]
+enum SigningKey {
+    /// JWT_PRIVATE_KEY, which signs access tokens
+    Jwt,
+    /// REFRESH_TOKEN_KEY, which hashes refresh tokens
+    Refresh
+}
+
+impl SigningKey {
+    fn env_var(&self) -> &'static str {
+        match self {
+            Self::Jwt => "JWT_PRIVATE_KEY",
+            Self::Refresh => "REFRESH_TOKEN_KEY"
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).is_err() {
+        eprintln!("A crypto provider is already installed, using it");
+    }
+
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    // Only the migrate command changes the schema; everything else refuses to run against an incompatible database
+    let run_migrations = matches!(cli.command, Command::Migrate { check: false });
+    let stores = connect_stores(run_migrations).await;
+
+    if let Err(e) = run(cli.command, &stores).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1)
+    }
+}
+
+async fn run(command: Command, stores: &Stores) -> Result<(), admin::AdminError> {
+    match command {
+        Command::Users(UserCommand::Create { username }) => {
+            let password = prompt_new_password();
+            let user_id = admin::create_user(stores, &username, &password).await?;
+            println!("Created {} with user_id {}", username, user_id);
+        },
+        Command::Users(UserCommand::Delete { username }) => {
+            admin::delete_user(stores, &username).await?;
+            println!("Deleted {}", username);
+        },
+        Command::Users(UserCommand::Purge { all }) => {
+            let count = admin::purge_deleted_users(stores, all).await?;
+            println!("Removed {} deleted users", count);
+        },
+        Command::Users(UserCommand::Disable { username }) => {
+            admin::set_disabled(stores, &username, true).await?;
+            println!("Disabled {} and revoked their sessions", username);
+        },
+        Command::Users(UserCommand::Enable { username }) => {
+            admin::set_disabled(stores, &username, false).await?;
+            println!("Enabled {}", username);
+        },
+        Command::Users(UserCommand::ResetPassword { username }) => {
+            let password = prompt_new_password();
+            admin::reset_password(stores, &username, &password).await?;
+            println!("Reset the password of {} and revoked their sessions", username);
+        },
+        Command::Sessions(SessionCommand::List { username }) => {
+            let sessions = admin::list_sessions(stores, &username).await?;
+            if sessions.is_empty() {
+                println!("{} has no active sessions", username);
+            }
+            for session in sessions {
+                println!("{}  refreshed {}  expires {}", session.session_id, session.created_at, session.expires_at);
+            }
+        },
+        Command::Sessions(SessionCommand::Revoke { session_id }) => {
+            admin::revoke_session(stores, session_id).await?;
+            println!("Revoked session {}", session_id);
+        },
+        Command::Sessions(SessionCommand::RevokeAll { username }) => {
+            admin::revoke_user_sessions(stores, &username).await?;
+            println!("Revoked every session of {}", username);
+        },
+        Command::Roles(RoleCommand::List { username }) => {
+            let roles = admin::list_roles(stores, &username).await?;
+            if roles.is_empty() {
+                println!("{} has no roles", username);
+            }
+            for role in roles {
+                println!("{}", role);
+            }
+        },
+        Command::Roles(RoleCommand::Add { username, role }) => {
+            admin::add_role(stores, &username, &role).await?;
+            println!("Added {} to {}", role, username);
+        },
+        Command::Roles(RoleCommand::Remove { username, role }) => {
+            admin::remove_role(stores, &username, &role).await?;
+            println!("Removed {} from {}", role, username);
+        },
+        Command::Keys(KeyCommand::Rotate { key }) => {
+            let new_key = admin::rotate_key(matches!(key, SigningKey::Jwt))?;
+            println!("Set the new key in .env and restart the server, then run 'keys revoke-sessions':");
+            println!("{}={}", key.env_var(), new_key);
+        },
+        Command::Keys(KeyCommand::RevokeSessions) => {
+            admin::revoke_all_sessions(stores).await?;
+            println!("Revoked every session");
+        },
+        Command::Migrate { .. } => println!("Database schema is up to date")
+    }
+
+    Ok(())
+}
+
+fn prompt_new_password() -> String {
+    let password = rpassword::prompt_password("Password: ").expect("Failed to read the password");
+    let confirmation = rpassword::prompt_password("Repeat password: ").expect("Failed to read the password");
+
+    if password != confirmation {
+        eprintln!("Error: the passwords do not match");
+        std::process::exit(1)
+    }
+    password
+}
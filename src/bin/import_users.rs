@@ -0,0 +1,45 @@
+use std::env;
+use std::path::PathBuf;
+
+use dotenv::dotenv;
+use rustls::crypto::CryptoProvider;
+
+use server::cli::import_users::{import_users, read_records};
+use server::db::connect::connect_stores;
+
+/// Bulk imports users from another system into the database picked with STORAGE_BACKEND, as for the server
+///
+/// Usage: import_users <file.csv|file.json>
+#[tokio::main]
+async fn main() {
+    if CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).is_err() {
+        eprintln!("A crypto provider is already installed, using it");
+    }
+
+    dotenv().ok();
+
+    let Some(path) = env::args().nth(1).map(PathBuf::from) else {
+        eprintln!("Usage: import_users <file.csv|file.json>");
+        std::process::exit(2)
+    };
+
+    let records = match read_records(&path) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            std::process::exit(1)
+        }
+    };
+
+    let stores = connect_stores(false).await;
+    let summary = import_users(records, stores.users.as_ref()).await;
+
+    println!("Imported {} users, {} already existed", summary.imported, summary.existing);
+    if !summary.unsupported.is_empty() {
+        println!("Skipped unsupported password hashes for: {}", summary.unsupported.join(", "));
+    }
+    if !summary.failed.is_empty() {
+        println!("Failed to import: {}", summary.failed.join(", "));
+        std::process::exit(1)
+    }
+}
@@ -0,0 +1,211 @@
+use std::env;
+
+use chrono::Duration;
+use uuid::Uuid;
+
+use crate::crypto::create_refresh::random_token;
+use crate::crypto::hash::PasswordHash;
+use crate::db::auth_events::{EventType, NewAuthEvent};
+use crate::db::create_refresh_entry::RefreshToken;
+use crate::db::store::{StoreError, Stores};
+use crate::db::user::{User, UserArgs};
+use crate::jobs;
+use crate::jobs::purge_deleted_users::PurgeConfig;
+
+/// Errors from the admin commands, shown to the operator
+#[derive(Debug)]
+pub enum AdminError {
+    UserNotFound(String),
+    UserExists(String),
+    EmptyPassword,
+    Store(StoreError),
+    Hashing(argon2::password_hash::Error),
+    Random(rustls::crypto::GetRandomFailed),
+    KeyPairAlgorithm(String)
+}
+
+impl std::fmt::Display for AdminError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UserNotFound(username) => write!(f, "no user named {}", username),
+            Self::UserExists(username) => write!(f, "a user named {} already exists", username),
+            Self::EmptyPassword => write!(f, "the password cannot be empty"),
+            Self::Store(e) => write!(f, "database error: {}", e),
+            Self::Hashing(e) => write!(f, "hashing error: {}", e),
+            Self::Random(e) => write!(f, "random generation failed: {:?}", e),
+            Self::KeyPairAlgorithm(algorithm) => write!(f, "JWT_ALGORITHM is {}, which signs with the key pair in JWT_PRIVATE_KEY_FILE rather than a secret; generate a new {} private key and point JWT_PRIVATE_KEY_FILE at it", algorithm, algorithm)
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<StoreError> for AdminError {
+    fn from(e: StoreError) -> Self {
+        Self::Store(e)
+    }
+}
+
+impl From<argon2::password_hash::Error> for AdminError {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        Self::Hashing(e)
+    }
+}
+
+impl From<rustls::crypto::GetRandomFailed> for AdminError {
+    fn from(e: rustls::crypto::GetRandomFailed) -> Self {
+        Self::Random(e)
+    }
+}
+
+/// Finds a user by username
+///
+/// # Arguments
+/// - `stores`: The stores to read from
+/// - `username`: The username to look up
+///
+/// # Returns
+/// The user, or AdminError::UserNotFound
+pub async fn find_user(stores: &Stores, username: &str) -> Result<User, AdminError> {
+    stores.users.read_user(username).await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AdminError::UserNotFound(username.to_string()))
+}
+
+/// Creates a user with a password hashed using the current settings
+///
+/// # Returns
+/// The user_id of the new user
+pub async fn create_user(stores: &Stores, username: &str, password: &str) -> Result<i64, AdminError> {
+    if password.is_empty() {
+        return Err(AdminError::EmptyPassword);
+    }
+    let hashed_password = PasswordHash::try_from(password)?;
+
+    stores.users.write_user(&UserArgs {
+        username: username.to_string(),
+        password: hashed_password.value()
+    }).await.map_err(|e| match e {
+        StoreError::Conflict => AdminError::UserExists(username.to_string()),
+        e => AdminError::from(e)
+    })
+}
+
+/// Deletes a user along with their sessions and roles straight away, without waiting for the grace period
+pub async fn delete_user(stores: &Stores, username: &str) -> Result<(), AdminError> {
+    let user = find_user(stores, username).await?;
+    stores.users.delete_user(user.user_id).await?;
+    Ok(())
+}
+
+/// Removes the deleted users whose grace period has passed, or every deleted user when all is set
+pub async fn purge_deleted_users(stores: &Stores, all: bool) -> Result<u64, AdminError> {
+    let grace_period = match all {
+        true => Duration::zero(),
+        false => PurgeConfig::from_env().grace_period
+    };
+
+    Ok(jobs::purge_deleted_users::purge_deleted_users(&stores.users, grace_period).await?)
+}
+
+/// Disables or re-enables a user. Disabling also revokes their sessions, and their current JWT is refused straight away
+pub async fn set_disabled(stores: &Stores, username: &str, disabled: bool) -> Result<(), AdminError> {
+    let user = find_user(stores, username).await?;
+    stores.users.set_user_disabled(user.user_id, disabled).await?;
+
+    if disabled {
+        stores.sessions.revoke_user_sessions(user.user_id).await?;
+    }
+
+    let event_type = match disabled {
+        true => EventType::AccountDisabled,
+        false => EventType::AccountEnabled
+    };
+    record_event(stores, event_type, user.user_id).await
+}
+
+/// Replaces a user's password and revokes their sessions
+pub async fn reset_password(stores: &Stores, username: &str, password: &str) -> Result<(), AdminError> {
+    if password.is_empty() {
+        return Err(AdminError::EmptyPassword);
+    }
+    let user = find_user(stores, username).await?;
+    let hashed_password = PasswordHash::try_from(password)?;
+
+    stores.users.update_password(user.user_id, &hashed_password.value()).await?;
+    stores.sessions.revoke_user_sessions(user.user_id).await?;
+    record_event(stores, EventType::PasswordChange, user.user_id).await
+}
+
+/// Finds the active sessions of a user
+///
+/// # Returns
+/// The current refresh token of each session, newest first
+pub async fn list_sessions(stores: &Stores, username: &str) -> Result<Vec<RefreshToken>, AdminError> {
+    let user = find_user(stores, username).await?;
+    Ok(stores.sessions.list_refresh_sessions(user.user_id).await?)
+}
+
+/// Revokes a single session
+pub async fn revoke_session(stores: &Stores, session_id: Uuid) -> Result<(), AdminError> {
+    stores.sessions.revoke_refresh_session(session_id).await?;
+    Ok(())
+}
+
+/// Revokes every session of a user
+pub async fn revoke_user_sessions(stores: &Stores, username: &str) -> Result<(), AdminError> {
+    let user = find_user(stores, username).await?;
+    stores.sessions.revoke_user_sessions(user.user_id).await?;
+    Ok(())
+}
+
+/// Finds the roles of a user
+pub async fn list_roles(stores: &Stores, username: &str) -> Result<Vec<String>, AdminError> {
+    let user = find_user(stores, username).await?;
+    Ok(stores.users.get_roles(user.user_id).await?)
+}
+
+/// Assigns a role to a user
+pub async fn add_role(stores: &Stores, username: &str, role: &str) -> Result<(), AdminError> {
+    let user = find_user(stores, username).await?;
+    stores.users.add_role(user.user_id, role).await?;
+    Ok(())
+}
+
+/// Removes a role from a user
+pub async fn remove_role(stores: &Stores, username: &str, role: &str) -> Result<(), AdminError> {
+    let user = find_user(stores, username).await?;
+    stores.users.remove_role(user.user_id, role).await?;
+    Ok(())
+}
+
+/// Generates a replacement for JWT_PRIVATE_KEY or REFRESH_TOKEN_KEY. Nothing changes until the key is set in the .env file and the server is restarted, after which revoke_all_sessions clears out the sessions that can no longer be refreshed
+///
+/// # Arguments
+/// - `signs_jwts`: Whether the key is JWT_PRIVATE_KEY, which is only a secret when JWT_ALGORITHM is HS256
+///
+/// # Returns
+/// The new key, or AdminError::KeyPairAlgorithm when JWTs are signed with a key pair
+pub fn rotate_key(signs_jwts: bool) -> Result<String, AdminError> {
+    if signs_jwts {
+        match env::var("JWT_ALGORITHM").as_deref() {
+            Err(_) | Ok("HS256") => (),
+            Ok(algorithm) => return Err(AdminError::KeyPairAlgorithm(algorithm.to_string()))
+        }
+    }
+
+    Ok(random_token()?)
+}
+
+/// Revokes every refresh session, for once the server has restarted with a rotated key and none of the existing sessions can be refreshed
+pub async fn revoke_all_sessions(stores: &Stores) -> Result<(), AdminError> {
+    stores.sessions.revoke_all_sessions().await?;
+    Ok(())
+}
+
+/// Records an event for a change made with the CLI, which has no client address or user agent
+async fn record_event(stores: &Stores, event_type: EventType, user_id: i64) -> Result<(), AdminError> {
+    stores.events.record_event(&NewAuthEvent { user_id: Some(user_id), event_type, ip_address: None, user_agent: None }).await?;
+    Ok(())
+}
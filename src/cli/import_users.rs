@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+use rocket::serde::Deserialize;
+use crate::crypto::hash::PasswordHash;
+use crate::db::store::{StoreError, UserStore};
+use crate::db::user::UserArgs;
+
+/// A user exported from another system. The password hash is stored as-is and upgraded to Argon2id on the user's next sign-in
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportRecord {
+    pub username: String,
+    pub password_hash: String
+}
+
+#[derive(Default, Debug)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub existing: usize,
+    pub unsupported: Vec<String>,
+    pub failed: Vec<String>
+}
+
+/// Reads users to import from a CSV file with a header row or a JSON array; the format is picked from the file extension
+///
+/// # Arguments
+/// - `path`: The path to a .csv or .json file with `username` and `password_hash` fields
+///
+/// # Returns
+/// A Result enum with the records in the file or the error from reading or parsing it
+pub fn read_records(path: &Path) -> Result<Vec<ImportRecord>, Box<dyn Error>> {
+    let file = File::open(path)?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => {
+            let mut reader = csv::Reader::from_reader(file);
+            let records = reader.deserialize().collect::<Result<Vec<ImportRecord>, _>>()?;
+            Ok(records)
+        },
+        Some("json") => Ok(serde_json::from_reader(file)?),
+        _ => Err(format!("{} must be a .csv or .json file", path.display()).into())
+    }
+}
+
+/// Writes imported users into the users table. Users whose username already exists are skipped, as are hashes in a format sign-in cannot verify
+///
+/// # Arguments
+/// - `records`: The users to import
+/// - `users`: The user store to write to
+///
+/// # Returns
+/// An ImportSummary with the number of users imported and the usernames that were not
+pub async fn import_users(records: Vec<ImportRecord>, users: &dyn UserStore) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+
+    for record in records {
+        if !PasswordHash::from(record.password_hash.clone()).is_supported() {
+            summary.unsupported.push(record.username);
+            continue;
+        }
+
+        match users.write_user(&UserArgs {
+            username: record.username.clone(),
+            password: record.password_hash
+        }).await {
+            Ok(_) => summary.imported += 1,
+            Err(StoreError::Conflict) => summary.existing += 1,
+            Err(e) => {
+                eprintln!("Failed to import {}: {}", record.username, e);
+                summary.failed.push(record.username);
+            }
+        }
+    }
+
+    summary
+}
@@ -0,0 +1,2 @@
+pub mod import_users;
+pub mod admin;
\ No newline at end of file
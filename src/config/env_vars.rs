@@ -0,0 +1,32 @@
+use std::env;
+use std::str::FromStr;
+
+/// Reads an optional environment variable and parses it into the requested type. The server exits if the variable is set but cannot be parsed, matching how required variables are handled
+///
+/// # Arguments
+/// - `name`: The name of the environment variable
+///
+/// # Returns
+/// The parsed value, or None if the variable is not set
+pub fn env_optional<T: FromStr>(name: &str) -> Option<T> {
+    let value = env::var(name).ok()?;
+
+    let Ok(value) = value.parse::<T>() else {
+        eprintln!("{} has an invalid value in .env", name);
+        std::process::exit(1)
+    };
+
+    Some(value)
+}
+
+/// Reads an environment variable, falling back to a default if it is not set
+///
+/// # Arguments
+/// - `name`: The name of the environment variable
+/// - `default`: The value used when the variable is not set
+///
+/// # Returns
+/// The parsed value or the default
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env_optional(name).unwrap_or(default)
+}
@@ -0,0 +1,2 @@
+pub mod env_vars;
+pub mod shutdown;
\ No newline at end of file
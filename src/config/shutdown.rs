@@ -0,0 +1,25 @@
+use rocket::config::Shutdown;
+use rocket::{Build, Rocket};
+
+use crate::config::env_vars::env_optional;
+
+/// Applies SHUTDOWN_GRACE_PERIOD and SHUTDOWN_MERCY_PERIOD (seconds) to Rocket's shutdown. Once SIGTERM or SIGINT is received, new connections are refused and in-flight requests get the grace period to finish, then open connections get the mercy period before they are closed. Rocket's defaults of 2 and 3 seconds are kept when the variables are not set
+///
+/// # Arguments
+/// - `rocket`: The rocket instance to configure
+///
+/// # Returns
+/// The rocket instance with its shutdown settings
+pub fn with_shutdown_config(rocket: Rocket<Build>) -> Rocket<Build> {
+    // The whole shutdown table is replaced, as setting only some of its keys would clear the signals it listens for
+    let mut shutdown: Shutdown = rocket.figment().extract_inner("shutdown").unwrap_or_default();
+    if let Some(grace) = env_optional("SHUTDOWN_GRACE_PERIOD") {
+        shutdown.grace = grace;
+    }
+    if let Some(mercy) = env_optional("SHUTDOWN_MERCY_PERIOD") {
+        shutdown.mercy = mercy;
+    }
+
+    let figment = rocket.figment().clone().merge(("shutdown", shutdown));
+    rocket.configure(figment)
+}
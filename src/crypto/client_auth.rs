@@ -0,0 +1,104 @@
+use std::env;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use subtle::ConstantTimeEq;
+
+/// A service allowed to call the token introspection and revocation routes, authenticated with HTTP Basic using a client id and secret from OAUTH_CLIENTS
+#[derive(Debug)]
+pub struct ApiClient {
+    pub client_id: String
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiClient {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some((client_id, secret)) = request.headers().get_one("Authorization").and_then(basic_credentials) else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+
+        match verify_client(&client_id, &secret) {
+            true => Outcome::Success(ApiClient { client_id }),
+            false => {
+                rocket::info!("Client authentication failed for {}", client_id);
+                Outcome::Error((Status::Unauthorized, ()))
+            }
+        }
+    }
+}
+
+/// A client from OAUTH_CLIENTS that is also listed in OAUTH_REVOKE_CLIENTS, and so may revoke any user's tokens through /revoke. Other clients are refused with 403
+#[derive(Debug)]
+pub struct RevokingClient {
+    pub client_id: String
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RevokingClient {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let client = rocket::outcome::try_outcome!(request.guard::<ApiClient>().await);
+
+        match may_revoke(&client.client_id) {
+            true => Outcome::Success(RevokingClient { client_id: client.client_id }),
+            false => {
+                rocket::info!("Client {} is not allowed to revoke tokens", client.client_id);
+                Outcome::Error((Status::Forbidden, ()))
+            }
+        }
+    }
+}
+
+/// Reads the client id and secret from a `Basic` Authorization header
+///
+/// # Arguments
+/// - `header`: The value of the Authorization header
+///
+/// # Returns
+/// The client id and secret, or None if the header is not valid Basic credentials
+fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (client_id, secret) = decoded.split_once(':')?;
+    Some((client_id.to_string(), secret.to_string()))
+}
+
+/// Checks a client id and secret against OAUTH_CLIENTS, a comma separated list of `client_id:secret` pairs. Secrets are compared in constant time
+///
+/// # Arguments
+/// - `client_id`: The client id sent by the caller
+/// - `secret`: The client secret sent by the caller
+///
+/// # Returns
+/// True if the client is listed with that secret
+pub fn verify_client(client_id: &str, secret: &str) -> bool {
+    let clients = env::var("OAUTH_CLIENTS").unwrap_or_default();
+
+    clients.split(',')
+        .filter_map(|client| client.trim().split_once(':'))
+        .any(|(id, expected)| id == client_id && !expected.is_empty() && bool::from(expected.as_bytes().ct_eq(secret.as_bytes())))
+}
+
+
+/// Checks a client id against OAUTH_REVOKE_CLIENTS, a comma separated list of the client ids from OAUTH_CLIENTS allowed to revoke tokens
+///
+/// # Arguments
+/// - `client_id`: The id of an authenticated client
+///
+/// # Returns
+/// True if the client may revoke tokens
+pub fn may_revoke(client_id: &str) -> bool {
+    let clients = env::var("OAUTH_REVOKE_CLIENTS").unwrap_or_default();
+
+    clients.split(',').any(|id| id.trim() == client_id)
+}
@@ -1,24 +1,183 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
 use rocket::http::{Cookie, SameSite};
 use rocket::time::Duration;
-use crate::crypto::hash::PasswordHash;
+use crate::config::env_vars::{env_optional, env_or};
+use crate::crypto::token_hash::hash_refresh_token;
+
+const HOST_PREFIX: &str = "__Host-";
+const REFRESH_COOKIE: &str = "refresh_token";
+
+/// How the expiry of a refresh session is extended when its token is rotated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionLifetime {
+    /// Every refresh pushes the expiry back to a full lifetime from now, so active sessions never expire
+    Sliding,
+    /// The expiry is fixed at sign-in and carried over to every rotated token
+    Absolute
+}
+
+impl FromStr for SessionLifetime {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sliding" => Ok(Self::Sliding),
+            "absolute" => Ok(Self::Absolute),
+            _ => Err(())
+        }
+    }
+}
+
+/// Refresh token cookie attributes and session lifetime, read from the environment
+#[derive(Debug, Clone)]
+pub struct RefreshConfig {
+    pub cookie_name: String,
+    pub secure: bool,
+    pub same_site: SameSite,
+    pub path: String,
+    pub domain: Option<String>,
+    pub lifetime: chrono::Duration,
+    pub mode: SessionLifetime
+}
+
+impl RefreshConfig {
+    pub fn from_env() -> Self {
+        let host_prefix = env_or("REFRESH_COOKIE_HOST_PREFIX", false);
+        let domain = env_optional::<String>("REFRESH_COOKIE_DOMAIN").filter(|domain| !domain.is_empty());
+
+        let same_site = match env_or("REFRESH_COOKIE_SAME_SITE", "strict".to_string()).to_ascii_lowercase().as_str() {
+            "strict" => SameSite::Strict,
+            "lax" => SameSite::Lax,
+            "none" => SameSite::None,
+            _ => {
+                eprintln!("REFRESH_COOKIE_SAME_SITE must be strict, lax or none in .env");
+                std::process::exit(1)
+            }
+        };
+
+        let lifetime = env_or::<i64>("REFRESH_TOKEN_LIFETIME", 30 * 24 * 60 * 60);
+        let Some(lifetime) = chrono::Duration::try_seconds(lifetime).filter(|lifetime| lifetime.num_seconds() > 0) else {
+            eprintln!("REFRESH_TOKEN_LIFETIME must be a positive number of seconds in .env");
+            std::process::exit(1)
+        };
+
+        let Some(mode) = env_optional::<String>("REFRESH_SESSION_MODE").map_or(Some(SessionLifetime::Sliding), |mode| mode.parse().ok()) else {
+            eprintln!("REFRESH_SESSION_MODE must be sliding or absolute in .env");
+            std::process::exit(1)
+        };
+
+        // Browsers only accept __Host- cookies that are secure, on the root path and without a domain
+        if host_prefix {
+            if domain.is_some() {
+                eprintln!("REFRESH_COOKIE_DOMAIN cannot be set with REFRESH_COOKIE_HOST_PREFIX in .env");
+                std::process::exit(1)
+            }
+
+            return Self {
+                cookie_name: format!("{}{}", HOST_PREFIX, REFRESH_COOKIE),
+                secure: true,
+                same_site,
+                path: "/".to_string(),
+                domain: None,
+                lifetime,
+                mode
+            };
+        }
 
-pub fn create_refresh() -> Result<(Cookie<'static>, String), rustls::crypto::GetRandomFailed> {
-    // Creating refresh token
+        Self {
+            cookie_name: REFRESH_COOKIE.to_string(),
+            secure: env_or("REFRESH_COOKIE_SECURE", true),
+            same_site,
+            path: env_or("REFRESH_COOKIE_PATH", "/refresh".to_string()),
+            domain,
+            lifetime,
+            mode
+        }
+    }
+
+    /// Gives the name to use for another session cookie, adding the __Host- prefix when the refresh cookie uses it
+    ///
+    /// # Arguments
+    /// - `name`: The unprefixed cookie name
+    ///
+    /// # Returns
+    /// The cookie name
+    pub fn prefixed_name(&self, name: &str) -> String {
+        if self.cookie_name.starts_with(HOST_PREFIX) {
+            format!("{}{}", HOST_PREFIX, name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Works out when the token replacing a refresh token should expire
+    ///
+    /// # Arguments
+    /// - `current_expiry`: The expiry of the token being replaced
+    /// - `now`: The current time
+    ///
+    /// # Returns
+    /// The expiry of the new token
+    pub fn rotated_expiry(&self, current_expiry: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self.mode {
+            SessionLifetime::Sliding => now + self.lifetime,
+            SessionLifetime::Absolute => current_expiry
+        }
+    }
+
+    /// Builds the refresh token cookie with the configured attributes
+    ///
+    /// # Arguments
+    /// - `value`: The value of the cookie, an empty value is used when removing it
+    /// - `expires_at`: When the refresh token expires on the server
+    ///
+    /// # Returns
+    /// The refresh token cookie
+    pub fn cookie(&self, value: String, expires_at: DateTime<Utc>) -> Cookie<'static> {
+        let max_age = (expires_at - Utc::now()).num_seconds().max(0);
+
+        let mut cookie = Cookie::build((self.cookie_name.clone(), value))
+             .http_only(true)
+             .secure(self.secure)
+             .same_site(self.same_site)
+             .path(self.path.clone())
+             .max_age(Duration::seconds(max_age))
+             .build();
+
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        cookie
+    }
+}
+
+/// Creates a new refresh token
+///
+/// # Arguments
+/// - `config`: The RefreshConfig used to build the cookie
+/// - `expires_at`: When the refresh token expires on the server, used as the cookie's max age
+///
+/// # Returns
+/// A Result enum with a cookie holding the raw token for the client and the hash of the token to store in the database, or a GetRandomFailed error if no randomness is available
+pub fn create_refresh(config: &RefreshConfig, expires_at: DateTime<Utc>) -> Result<(Cookie<'static>, String), rustls::crypto::GetRandomFailed> {
+    let refresh_token = random_token()?;
+    let refresh_hash = hash_refresh_token(&refresh_token);
+
+    Ok((config.cookie(refresh_token, expires_at), refresh_hash))
+}
+
+/// Creates a random hex encoded 256 bit token
+///
+/// # Returns
+/// A Result enum with the token or a GetRandomFailed error if no randomness is available
+pub fn random_token() -> Result<String, rustls::crypto::GetRandomFailed> {
     let mut bytes = [0u8; 32]; // 256 bits
     let provider = rustls::crypto::ring::default_provider();
     let rng = &provider.secure_random;
 
-    let _ = rng.fill(&mut bytes)?;
-    let refresh_token = hex::encode(bytes);
-    let refresh_hash = PasswordHash::try_from(refresh_token.as_str()).expect("asd");
-
-    let cookie = Cookie::build(("refresh_token", refresh_hash.value().clone()))
-         .http_only(true)
-         .secure(false)
-         .same_site(SameSite::Lax)
-         .path("/refresh")
-         .max_age(Duration::days(30))
-         .finish();
-
-    Ok((cookie, refresh_hash.value()))
-}
\ No newline at end of file
+    rng.fill(&mut bytes)?;
+    Ok(hex::encode(bytes))
+}
@@ -0,0 +1,35 @@
+use rocket::http::{Method, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use subtle::ConstantTimeEq;
+
+use crate::crypto::create_refresh::RefreshConfig;
+use crate::crypto::session_cookies::csrf_cookie_name;
+
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Request guard for state-changing routes, using the double-submit pattern. Requests authenticated with an Authorization header pass, as browsers never attach one on their own; otherwise the X-CSRF-Token header must match the CSRF token cookie, which only pages on our own origin can read
+pub struct CsrfGuard;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfGuard {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if matches!(request.method(), Method::Get | Method::Head | Method::Options)
+            || request.headers().contains("Authorization") {
+            return Outcome::Success(CsrfGuard);
+        }
+
+        let config = RefreshConfig::from_env();
+        let cookie = request.cookies().get(&csrf_cookie_name(&config));
+        let header = request.headers().get_one(CSRF_HEADER);
+
+        match (cookie, header) {
+            (Some(cookie), Some(header)) if !header.is_empty() && bool::from(cookie.value().as_bytes().ct_eq(header.as_bytes())) => {
+                Outcome::Success(CsrfGuard)
+            },
+            _ => Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
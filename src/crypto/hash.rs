@@ -1,11 +1,114 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 use argon2::{
     password_hash::{PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, KeyId, Params, ParamsBuilder, Version,
 };
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use pbkdf2::Pbkdf2;
 use rand_core::OsRng;
+use scrypt::Scrypt;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::config::env_vars::{env_optional, env_or};
+use crate::metrics::collectors::time_hashing;
 
 pub struct PasswordHash(String);
 
+/// Argon2 settings used for new hashes, and the peppers hashes can be checked with
+///
+/// ARGON2_MEMORY_COST (KiB), ARGON2_TIME_COST and ARGON2_PARALLELISM default to the argon2 crate defaults. PASSWORD_PEPPER is an optional server-side secret mixed into every new hash, and PASSWORD_PEPPER_ID labels it inside the stored hash. PASSWORD_PREVIOUS_PEPPERS is a comma separated list of `id:secret` pairs for peppers that were used before, so hashes made with them can still be checked after the pepper is rotated
+pub struct HashConfig {
+    pub params: Params,
+    pepper: Option<String>,
+    /// Every known pepper by its id, including the current one
+    peppers: HashMap<String, String>
+}
+
+impl HashConfig {
+    /// # Arguments
+    /// - `params`: The Argon2 costs for new hashes
+    /// - `pepper`: The id and secret of the pepper new hashes are made with, if any
+    /// - `previous_peppers`: The secrets of peppers older hashes may have been made with, by their id
+    ///
+    /// # Returns
+    /// The settings, or an error if a pepper id is too long
+    pub fn new(params: Params, pepper: Option<(String, String)>, previous_peppers: HashMap<String, String>) -> Result<Self, String> {
+        let mut peppers = previous_peppers;
+        let Some((pepper_id, pepper)) = pepper else {
+            return Ok(Self { params, pepper: None, peppers });
+        };
+
+        let key_id_error = || format!("PASSWORD_PEPPER_ID must be at most {} bytes", Params::MAX_KEYID_LEN);
+        let params = ParamsBuilder::new()
+            .m_cost(params.m_cost())
+            .t_cost(params.t_cost())
+            .p_cost(params.p_cost())
+            .keyid(KeyId::new(pepper_id.as_bytes()).map_err(|_| key_id_error())?)
+            .build()
+            .map_err(|_| key_id_error())?;
+        peppers.insert(pepper_id, pepper.clone());
+
+        Ok(Self { params, pepper: Some(pepper), peppers })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let pepper = env_optional::<String>("PASSWORD_PEPPER").filter(|pepper| !pepper.is_empty());
+        let pepper_id = env_or("PASSWORD_PEPPER_ID", "1".to_string());
+        let previous_peppers = env_or("PASSWORD_PREVIOUS_PEPPERS", String::new())
+            .split(',')
+            .filter_map(|pepper| pepper.trim().split_once(':'))
+            .filter(|(_, secret)| !secret.is_empty())
+            .map(|(id, secret)| (id.to_string(), secret.to_string()))
+            .collect();
+
+        let params = ParamsBuilder::new()
+            .m_cost(env_or("ARGON2_MEMORY_COST", Params::DEFAULT_M_COST))
+            .t_cost(env_or("ARGON2_TIME_COST", Params::DEFAULT_T_COST))
+            .p_cost(env_or("ARGON2_PARALLELISM", Params::DEFAULT_P_COST))
+            .build()
+            .map_err(|_| "ARGON2_MEMORY_COST, ARGON2_TIME_COST or ARGON2_PARALLELISM is out of range".to_string())?;
+
+        Self::new(params, pepper.map(|pepper| (pepper_id, pepper)), previous_peppers)
+    }
+
+    fn argon2(&self) -> Result<Argon2<'_>, argon2::password_hash::Error> {
+        match &self.pepper {
+            Some(pepper) => Ok(Argon2::new_with_secret(
+                pepper.as_bytes(), Algorithm::Argon2id, Version::V0x13, self.params.clone()
+            )?),
+            None => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone()))
+        }
+    }
+
+    /// The Argon2 instance that checks a hash made with the pepper labelled key_id
+    ///
+    /// # Returns
+    /// The instance, or None if no pepper with that id is configured
+    fn argon2_for(&self, key_id: &str) -> Option<Result<Argon2<'_>, argon2::password_hash::Error>> {
+        let pepper = self.peppers.get(key_id)?;
+
+        Some(Argon2::new_with_secret(pepper.as_bytes(), Algorithm::Argon2id, Version::V0x13, self.params.clone())
+            .map_err(argon2::password_hash::Error::from))
+    }
+}
+
+/// The settings read from the environment on first use. The server exits if they are invalid, as it cannot hash passwords without them
+pub fn hash_config() -> &'static HashConfig {
+    static CONFIG: OnceLock<HashConfig> = OnceLock::new();
+
+    CONFIG.get_or_init(|| match HashConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} in .env", e);
+            std::process::exit(1)
+        }
+    })
+}
+
 impl From<String> for PasswordHash {
     fn from(s: String) -> Self {
         Self(s)
@@ -15,28 +118,186 @@ impl From<String> for PasswordHash {
 impl TryFrom<&str> for PasswordHash {
     type Error = argon2::password_hash::Error;
 
+    #[tracing::instrument(name = "PasswordHash::try_from", skip_all)]
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let hashed_pwd = argon2.hash_password(s.as_bytes(), &salt)?;
-        let hashed_pwd = hashed_pwd.to_string();
-
-        Ok(Self(hashed_pwd))
+        Self::hash_with(s, hash_config())
     }
 }
 
 impl PasswordHash {
+    /// Hashes a password with Argon2id using the given settings rather than the ones read from the environment
+    ///
+    /// # Arguments
+    /// - `password`: The plain password
+    /// - `config`: The Argon2 costs and pepper to hash with
+    ///
+    /// # Returns
+    /// The hash, or an argon2 error
+    pub fn hash_with(password: &str, config: &HashConfig) -> Result<Self, argon2::password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let argon2 = config.argon2()?;
+        let hashed_pwd = time_hashing("hash", || argon2.hash_password(password.as_bytes(), &salt))?;
+
+        Ok(Self(hashed_pwd.to_string()))
+    }
+
     pub fn value(&self) -> String {
         self.0.clone()
     }
 
+    /// Checks if the stored hash uses a format that verify understands; Argon2 as well as bcrypt, scrypt and PBKDF2-SHA256 hashes imported from older systems
+    ///
+    /// # Returns
+    /// True if the hash format is supported
+    pub fn is_supported(&self) -> bool {
+        let binding = self.value();
+        if is_bcrypt(&binding) {
+            return binding.parse::<bcrypt::HashParts>().is_ok();
+        }
+        if passlib_pbkdf2(&binding).is_some() {
+            return true;
+        }
+
+        match argon2::PasswordHash::new(binding.as_str()) {
+            Ok(parsed_hash) => matches!(
+                parsed_hash.algorithm.as_str(),
+                "argon2id" | "argon2i" | "argon2d" | "scrypt" | "pbkdf2-sha256"
+            ),
+            Err(_) => false
+        }
+    }
+
+    #[tracing::instrument(name = "PasswordHash::verify", skip_all)]
     pub fn verify(&self, password: &String) -> Result<bool, argon2::password_hash::Error> {
-        let argon2 = Argon2::default();
+        self.verify_with(password, hash_config())
+    }
+
+    /// Checks a password against the hash, looking up the pepper by the key id stored in the hash
+    ///
+    /// # Arguments
+    /// - `password`: The plain password
+    /// - `config`: The peppers the hash may have been made with
+    ///
+    /// # Returns
+    /// True if the password matches, false if it does not or the hash's pepper is not configured, or an argon2 error if the hash cannot be parsed
+    pub fn verify_with(&self, password: &String, config: &HashConfig) -> Result<bool, argon2::password_hash::Error> {
+        time_hashing("verify", || self.verify_untimed(password, config))
+    }
+
+    fn verify_untimed(&self, password: &String, config: &HashConfig) -> Result<bool, argon2::password_hash::Error> {
         let binding = self.value();
+
+        // bcrypt uses the modular crypt format rather than a PHC string
+        if is_bcrypt(&binding) {
+            return Ok(bcrypt::verify(password, &binding).unwrap_or(false));
+        }
+        if let Some(hash) = passlib_pbkdf2(&binding) {
+            return Ok(hash.verify(password));
+        }
+
         let parsed_hash = argon2::PasswordHash::new(binding.as_str())?;
+
+        let legacy_verifier: Option<&dyn PasswordVerifier> = match parsed_hash.algorithm.as_str() {
+            "scrypt" => Some(&Scrypt),
+            "pbkdf2-sha256" => Some(&Pbkdf2),
+            _ => None
+        };
+        if let Some(verifier) = legacy_verifier {
+            return Ok(verifier.verify_password(password.as_bytes(), &parsed_hash).is_ok());
+        }
+
+        // Hashes carrying a key id were made with the pepper of that id, older ones were not
+        let params = Params::try_from(&parsed_hash)?;
+        let argon2 = match params.keyid() {
+            [] => Argon2::default(),
+            key_id => {
+                let key_id = String::from_utf8_lossy(key_id);
+                match config.argon2_for(&key_id) {
+                    Some(argon2) => argon2?,
+                    None => {
+                        rocket::error!("No pepper with id {} is configured, add it to PASSWORD_PREVIOUS_PEPPERS", key_id);
+                        return Ok(false);
+                    }
+                }
+            }
+        };
+
         match argon2.verify_password(password.as_bytes(), &parsed_hash) {
             Ok(_) => Ok(true),
             Err(_) => Ok(false)
         }
     }
-}
\ No newline at end of file
+
+    /// Checks if the stored hash is not Argon2id or was made with different settings than the ones currently configured. Used on sign-in to upgrade old hashes once the plain password is known
+    ///
+    /// # Returns
+    /// True if the password should be hashed again with the current settings
+    pub fn needs_rehash(&self) -> bool {
+        self.needs_rehash_with(hash_config())
+    }
+
+    /// needs_rehash, comparing against the given settings rather than the ones read from the environment
+    pub fn needs_rehash_with(&self, config: &HashConfig) -> bool {
+        let binding = self.value();
+        let Ok(parsed_hash) = argon2::PasswordHash::new(binding.as_str()) else {
+            return true;
+        };
+
+        if parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        match Params::try_from(&parsed_hash) {
+            Ok(params) => {
+                params.m_cost() != config.params.m_cost()
+                    || params.t_cost() != config.params.t_cost()
+                    || params.p_cost() != config.params.p_cost()
+                    || params.keyid() != config.params.keyid()
+            },
+            Err(_) => true
+        }
+    }
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix))
+}
+
+/// A PBKDF2-SHA256 hash in the format written by Python's passlib
+struct PasslibPbkdf2 {
+    rounds: u32,
+    salt: Vec<u8>,
+    hash: Vec<u8>
+}
+
+impl PasslibPbkdf2 {
+    fn verify(&self, password: &str) -> bool {
+        let mut derived = vec![0u8; self.hash.len()];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &self.salt, self.rounds, &mut derived);
+
+        derived.ct_eq(&self.hash).into()
+    }
+}
+
+/// Parses a passlib hash, `$pbkdf2-sha256$<rounds>$<salt>$<hash>`. Unlike a PHC string the rounds have no `i=` name, and the salt and hash use passlib's base64 alphabet, which has `.` in place of `+`
+///
+/// # Returns
+/// The parsed hash, or None if the string is not in this format
+fn passlib_pbkdf2(hash: &str) -> Option<PasslibPbkdf2> {
+    let mut parts = hash.strip_prefix("$pbkdf2-sha256$")?.split('$');
+    let (Some(rounds), Some(salt), Some(hash), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    if rounds.is_empty() || !rounds.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let decode = |value: &str| STANDARD_NO_PAD.decode(value.replace('.', "+")).ok();
+    let (salt, hash) = (decode(salt)?, decode(hash)?);
+    if hash.is_empty() {
+        return None;
+    }
+
+    Some(PasslibPbkdf2 { rounds: rounds.parse().ok().filter(|rounds| *rounds > 0)?, salt, hash })
+}
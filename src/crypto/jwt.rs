@@ -1,39 +1,117 @@
-use jsonwebtoken::{Header, encode, EncodingKey, DecodingKey, Validation, Algorithm, decode};
-use rocket::serde::{Deserialize, Serialize};
+use jsonwebtoken::jwk::{Jwk, JwkSet, PublicKeyUse, ThumbprintHash};
+use jsonwebtoken::{Header, encode, EncodingKey, DecodingKey, Algorithm};
 use std::env;
+use std::fs;
+use std::sync::OnceLock;
+use uuid::Uuid;
 
-#[derive(Debug)]
-pub enum JwtStatus {
-    Valid(Claims),
-    Expired(Claims)
+use auth_guard::verify::decode_with_key;
+
+pub use auth_guard::claims::{Claims, JwtStatus};
+
+/// The keys JWTs are signed and verified with. JWT_ALGORITHM picks HS256 (the default), which signs with the JWT_PRIVATE_KEY secret, or RS256 or ES256, which sign with the PEM private key in the file at JWT_PRIVATE_KEY_FILE and publish its public key at /.well-known/jwks.json
+pub struct JwtKeys {
+    pub algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    /// The public key with its RFC 7638 thumbprint as the kid, or None for HS256 as the secret must not be published
+    pub public_jwk: Option<Jwk>
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Claims {
-    pub user_id: i64,
-    pub username: String,
-    pub exp: i64
+impl JwtKeys {
+    /// Reads the keys from the environment
+    ///
+    /// # Returns
+    /// The keys, or a message naming the variable that is missing or invalid
+    pub fn from_env() -> Result<Self, String> {
+        let algorithm = match env::var("JWT_ALGORITHM").as_deref() {
+            Err(_) | Ok("HS256") => Algorithm::HS256,
+            Ok("RS256") => Algorithm::RS256,
+            Ok("ES256") => Algorithm::ES256,
+            Ok(_) => return Err("JWT_ALGORITHM must be HS256, RS256 or ES256".to_string())
+        };
+
+        if algorithm == Algorithm::HS256 {
+            let private_key = match env::var("JWT_PRIVATE_KEY") {
+                Ok(key) if !key.is_empty() => key,
+                _ => return Err("JWT_PRIVATE_KEY is not set".to_string())
+            };
+            return Ok(Self {
+                algorithm,
+                encoding_key: EncodingKey::from_secret(private_key.as_bytes()),
+                decoding_key: DecodingKey::from_secret(private_key.as_bytes()),
+                public_jwk: None
+            });
+        }
+
+        let path = env::var("JWT_PRIVATE_KEY_FILE").map_err(|_| "JWT_PRIVATE_KEY_FILE is not set".to_string())?;
+        let pem = fs::read(&path).map_err(|e| format!("JWT_PRIVATE_KEY_FILE cannot be read: {}", e))?;
+        let invalid_key = |e: jsonwebtoken::errors::Error| format!("JWT_PRIVATE_KEY_FILE is not a {:?} private key: {}", algorithm, e);
+        let encoding_key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem),
+            _ => EncodingKey::from_ec_pem(&pem)
+        }.map_err(invalid_key)?;
+
+        let mut public_jwk = Jwk::from_encoding_key(&encoding_key, algorithm).map_err(invalid_key)?;
+        public_jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        public_jwk.common.key_id = Some(public_jwk.thumbprint(ThumbprintHash::SHA256));
+        let decoding_key = DecodingKey::from_jwk(&public_jwk).map_err(invalid_key)?;
+
+        Ok(Self { algorithm, encoding_key, decoding_key, public_jwk: Some(public_jwk) })
+    }
+
+    /// The kid set in the header of every JWT, None for HS256
+    pub fn key_id(&self) -> Option<String> {
+        self.public_jwk.as_ref().and_then(|jwk| jwk.common.key_id.clone())
+    }
 }
 
-pub fn create_jwt(username: String, user_id: i64) -> String {
+/// The keys read from the environment on first use. The server exits if they are missing or invalid, as it cannot issue tokens without them
+pub fn jwt_keys() -> &'static JwtKeys {
+    static KEYS: OnceLock<JwtKeys> = OnceLock::new();
+
+    KEYS.get_or_init(|| match JwtKeys::from_env() {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("{} in .env", e);
+            std::process::exit(1)
+        }
+    })
+}
+
+/// The public keys other services verify JWTs with, empty for HS256
+pub fn jwks() -> JwkSet {
+    JwkSet { keys: jwt_keys().public_jwk.iter().cloned().collect() }
+}
+
+#[tracing::instrument(skip_all, fields(user_id, session_id = %session_id))]
+pub fn create_jwt(username: String, user_id: i64, session_id: Uuid, roles: &[String]) -> String {
     // Creates a JWT for a user to be used for authentication into protected routes
     //
     // args
     // username: A string of the username of the user creating the JWT
+    // user_id: The id of the user creating the JWT
+    // session_id: The refresh session the JWT belongs to
+    // roles: The user's roles, sent as the scope claim so other services can check them
     //
     // returns
     // A string with is the JWT token
 
-    let (private_key, jwt_expire_time) = get_env_vars();
+    let keys = jwt_keys();
+    let mut header = Header::new(keys.algorithm);
+    header.kid = keys.key_id();
 
     encode(
-        &Header::default(),
+        &header,
         &Claims {
-            user_id: user_id,
-            username: username,
-            exp: chrono::Utc::now().timestamp().checked_add(jwt_expire_time).unwrap()
+            user_id,
+            username,
+            exp: chrono::Utc::now().timestamp().checked_add(jwt_expire_time()).unwrap(),
+            session_id: Some(session_id),
+            jti: Some(Uuid::new_v4()),
+            scope: roles.join(" ")
         },
-        &EncodingKey::from_secret(&private_key.to_string().into_bytes())
+        &keys.encoding_key
     ).unwrap()
 }
 
@@ -46,27 +124,26 @@ pub fn decode_jwt(token: &str) -> Result<JwtStatus, jsonwebtoken::errors::Error>
     // returns
     // A result enum with the Claims struct used to create the JWT or a jsonwebtoken error enum
 
-    let (private_key, _jwt_expire_time) = get_env_vars();
-
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.validate_exp = false;
+    let keys = jwt_keys();
 
-    let user_data = decode::<Claims>(
-        &token, &DecodingKey::from_secret(&private_key.to_string().into_bytes()), &validation
-    )?;
+    decode_with_key(token, &keys.decoding_key, keys.algorithm)
+}
 
-    if user_data.claims.exp <= chrono::Utc::now().timestamp() {
-        Ok(JwtStatus::Expired(user_data.claims))
-    } else {
-        Ok(JwtStatus::Valid(user_data.claims))
+/// Checks that the signing key and JWT_EXPIRE_TIME are set, without exiting like the functions that use them
+///
+/// # Returns
+/// Nothing, or a message naming the variable that is missing or invalid
+pub fn check_jwt_config() -> Result<(), String> {
+    JwtKeys::from_env()?;
+    match env::var("JWT_EXPIRE_TIME").map(|time| time.parse::<i64>()) {
+        Ok(Ok(time)) if time > 0 => Ok(()),
+        Ok(_) => Err("JWT_EXPIRE_TIME is not a positive integer".to_string()),
+        Err(_) => Err("JWT_EXPIRE_TIME is not set".to_string())
     }
 }
 
-fn get_env_vars() -> (String, i64) {
-    let Ok(private_key) = env::var("JWT_PRIVATE_KEY") else {
-        eprintln!("JWT_PRIVATE_KEY is not set in .env");
-        std::process::exit(1)
-    };
+/// The number of seconds a new JWT is valid for, set with JWT_EXPIRE_TIME
+pub fn jwt_expire_time() -> i64 {
     let Ok(jwt_expire_time) = env::var("JWT_EXPIRE_TIME") else {
         eprintln!("JWT_EXPIRE_TIME is not set in .env");
         std::process::exit(1)
@@ -76,5 +153,5 @@ fn get_env_vars() -> (String, i64) {
         std::process::exit(1)
     };
 
-    (private_key, jwt_expire_time)
-}
\ No newline at end of file
+    jwt_expire_time
+}
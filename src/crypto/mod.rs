@@ -1,3 +1,7 @@
 pub mod hash;
 pub mod jwt;
-pub mod create_refresh;
\ No newline at end of file
+pub mod create_refresh;
+pub mod token_hash;
+pub mod session_cookies;
+pub mod csrf;
+pub mod client_auth;
\ No newline at end of file
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use rocket::http::{Cookie, CookieJar, Status};
+use rocket::time::Duration;
+use rocket::Request;
+
+use crate::config::env_vars::env_or;
+use crate::crypto::create_refresh::{random_token, RefreshConfig};
+
+const ACCESS_COOKIE: &str = "access_token";
+const CSRF_COOKIE: &str = "csrf_token";
+
+/// Checks if browser session mode is on, set with ACCESS_TOKEN_COOKIE. In this mode sign-in and refresh deliver the JWT in an HTTP-Only cookie instead of the response body, along with a CSRF token cookie readable by JavaScript
+pub fn cookie_mode() -> bool {
+    env_or("ACCESS_TOKEN_COOKIE", false)
+}
+
+pub fn access_cookie_name(config: &RefreshConfig) -> String {
+    config.prefixed_name(ACCESS_COOKIE)
+}
+
+pub fn csrf_cookie_name(config: &RefreshConfig) -> String {
+    config.prefixed_name(CSRF_COOKIE)
+}
+
+/// Adds the access token and CSRF token cookies for browser session mode. Both use the refresh cookie's attributes on the root path and last as long as the refresh session, as refresh needs the expired JWT
+///
+/// # Arguments
+/// - `jar`: A reference to the cookie jar provided by rocket
+/// - `config`: The RefreshConfig the cookie attributes are taken from
+/// - `token`: The JWT
+/// - `expires_at`: When the refresh session expires
+///
+/// # Returns
+/// A Result enum with no data or a GetRandomFailed error if the CSRF token could not be created
+pub fn add_session_cookies(jar: &CookieJar<'_>, config: &RefreshConfig, token: String, expires_at: DateTime<Utc>) -> Result<(), rustls::crypto::GetRandomFailed> {
+    let csrf_token = random_token()?;
+
+    jar.add_private(session_cookie(config, access_cookie_name(config), token, expires_at, true));
+    jar.add(session_cookie(config, csrf_cookie_name(config), csrf_token, expires_at, false));
+
+    Ok(())
+}
+
+/// Removes the access token and CSRF token cookies
+///
+/// # Arguments
+/// - `jar`: A reference to the cookie jar provided by rocket
+/// - `config`: The RefreshConfig the cookie attributes are taken from
+pub fn remove_session_cookies(jar: &CookieJar<'_>, config: &RefreshConfig) {
+    let now = Utc::now();
+    jar.remove_private(session_cookie(config, access_cookie_name(config), String::new(), now, true));
+    jar.remove(session_cookie(config, csrf_cookie_name(config), String::new(), now, false));
+}
+
+/// Finds the JWT of a request, from the Authorization header or otherwise the access token cookie
+///
+/// # Arguments
+/// - `request`: The incoming request
+///
+/// # Returns
+/// A Result enum with the JWT, or BadRequest if the Authorization header is not a Bearer token and Unauthorized if there is no token
+pub fn access_token(request: &Request<'_>) -> Result<String, Status> {
+    if let Some(header) = request.headers().get_one("Authorization") {
+        return header.strip_prefix("Bearer ")
+            .map(|token| token.to_string())
+            .ok_or(Status::BadRequest);
+    }
+
+    let config = RefreshConfig::from_env();
+    request.cookies().get_private(&access_cookie_name(&config))
+        .map(|cookie| cookie.value().to_string())
+        .ok_or(Status::Unauthorized)
+}
+
+fn session_cookie(config: &RefreshConfig, name: String, value: String, expires_at: DateTime<Utc>, http_only: bool) -> Cookie<'static> {
+    let max_age = (expires_at - Utc::now()).num_seconds().max(0);
+
+    let mut cookie = Cookie::build((name, value))
+         .http_only(http_only)
+         .secure(config.secure)
+         .same_site(config.same_site)
+         .path("/")
+         .max_age(Duration::seconds(max_age))
+         .build();
+
+    if let Some(domain) = &config.domain {
+        cookie.set_domain(domain.clone());
+    }
+
+    cookie
+}
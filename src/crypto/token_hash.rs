@@ -0,0 +1,81 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hashes a refresh token with HMAC-SHA256 under the server's REFRESH_TOKEN_KEY. Refresh tokens are 256 bits of randomness so a slow password hash adds nothing, and a keyed hash means a leaked database alone cannot be used to forge lookups
+///
+/// # Arguments
+/// - `token`: The raw refresh token sent to the client
+///
+/// # Returns
+/// The hex encoded hash which is stored in and looked up from the database
+pub fn hash_refresh_token(token: &str) -> String {
+    hex::encode(refresh_mac(token).finalize().into_bytes())
+}
+
+/// Checks a raw refresh token against a stored hash in constant time
+///
+/// # Arguments
+/// - `token`: The raw refresh token sent by the client
+/// - `stored_hash`: The hex encoded hash from the database
+///
+/// # Returns
+/// True if the token matches the stored hash
+pub fn verify_refresh_token(token: &str, stored_hash: &str) -> bool {
+    let Ok(stored_hash) = hex::decode(stored_hash) else {
+        return false;
+    };
+
+    refresh_mac(token).verify_slice(&stored_hash).is_ok()
+}
+
+/// Checks that REFRESH_TOKEN_KEY is set, without exiting like the functions that use it
+///
+/// # Returns
+/// Nothing, or a message saying the key is missing
+pub fn check_refresh_token_key() -> Result<(), String> {
+    match env::var("REFRESH_TOKEN_KEY") {
+        Ok(key) if !key.is_empty() => Ok(()),
+        _ => Err("REFRESH_TOKEN_KEY is not set".to_string())
+    }
+}
+
+fn refresh_mac(token: &str) -> HmacSha256 {
+    let Ok(key) = env::var("REFRESH_TOKEN_KEY") else {
+        eprintln!("REFRESH_TOKEN_KEY is not set in .env");
+        std::process::exit(1)
+    };
+
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(token.as_bytes());
+    mac
+}
+
+/// Hashes an email verification token with the same key as refresh tokens. The token is prefixed first so a token of one kind can never be accepted as the other
+///
+/// # Arguments
+/// - `token`: The raw verification token sent to the user
+///
+/// # Returns
+/// The hex encoded hash which is stored in the database
+pub fn hash_email_token(token: &str) -> String {
+    hash_refresh_token(&email_token_input(token))
+}
+
+/// Checks a raw email verification token against a stored hash in constant time
+///
+/// # Arguments
+/// - `token`: The raw verification token sent by the user
+/// - `stored_hash`: The hex encoded hash from the database
+///
+/// # Returns
+/// True if the token matches the stored hash
+pub fn verify_email_token(token: &str, stored_hash: &str) -> bool {
+    verify_refresh_token(&email_token_input(token), stored_hash)
+}
+
+fn email_token_input(token: &str) -> String {
+    format!("email-verification:{}", token)
+}
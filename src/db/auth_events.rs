@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+
+/// The kinds of authentication event recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Signup,
+    SigninSuccess,
+    SigninFailure,
+    Refresh,
+    /// A refresh token that had already been replaced was presented, so its session was revoked
+    RefreshReuse,
+    Logout,
+    PasswordChange,
+    UsernameChange,
+    EmailChange,
+    /// The account was disabled with the admin CLI, not by repeated signin failures as there is no lockout
+    AccountDisabled,
+    AccountEnabled,
+    AccountDeleted
+}
+
+impl EventType {
+    /// The name stored in the event_type column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Signup => "signup",
+            Self::SigninSuccess => "signin_success",
+            Self::SigninFailure => "signin_failure",
+            Self::Refresh => "refresh",
+            Self::RefreshReuse => "refresh_reuse",
+            Self::Logout => "logout",
+            Self::PasswordChange => "password_change",
+            Self::UsernameChange => "username_change",
+            Self::EmailChange => "email_change",
+            Self::AccountDisabled => "account_disabled",
+            Self::AccountEnabled => "account_enabled",
+            Self::AccountDeleted => "account_deleted"
+        }
+    }
+}
+
+/// A recorded authentication event. user_id is None for failed sign-ins to accounts that do not exist
+#[derive(FromRow, Deserialize, Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthEvent {
+    pub event_id: i64,
+    pub user_id: Option<i64>,
+    pub event_type: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>
+}
+
+/// An event to record; the id and time are set by the store
+#[derive(Debug, Clone)]
+pub struct NewAuthEvent {
+    pub user_id: Option<i64>,
+    pub event_type: EventType,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>
+}
+
+/// Records an authentication event in a PostgreSQL database
+///
+/// # Arguments
+/// - `event`: The event to record
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn record_event(event: &NewAuthEvent, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            INSERT INTO auth_events (user_id, event_type, ip_address, user_agent)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            event.user_id,
+            event.event_type.as_str(),
+            event.ip_address,
+            event.user_agent,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Finds a page of authentication events, newest first
+///
+/// # Arguments
+/// - `user_id`: Only the events of this user, or every event when None
+/// - `limit`: The number of events to return
+/// - `offset`: The number of events to skip
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the events or an sql::error::Error enum if the operation is not successful
+pub async fn list_events(user_id: Option<i64>, limit: i64, offset: i64, pool: &PgPool) -> Result<Vec<AuthEvent>,  sqlx::error::Error> {
+    let events = sqlx::query_as!(
+            AuthEvent,
+            r#"
+            SELECT * FROM auth_events
+            WHERE $1::bigint IS NULL OR user_id = $1
+            ORDER BY created_at DESC, event_id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit,
+            offset
+            )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(events)
+}
+
+/// Counts the authentication events in the same way as list_events
+///
+/// # Arguments
+/// - `user_id`: Only the events of this user, or every event when None
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the number of events or an sql::error::Error enum if the operation is not successful
+pub async fn count_events(user_id: Option<i64>, pool: &PgPool) -> Result<i64,  sqlx::error::Error> {
+    let count = sqlx::query_scalar!(
+            r#"
+            SELECT count(*) AS "count!" FROM auth_events
+            WHERE $1::bigint IS NULL OR user_id = $1
+            "#,
+            user_id
+            )
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count)
+}
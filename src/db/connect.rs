@@ -0,0 +1,179 @@
+use std::env;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+use sqlx::pool::PoolOptions;
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Database, Pool};
+
+use crate::config::env_vars::env_or;
+use crate::db::migrations::{prepare_schema, POSTGRES_MIGRATOR};
+use crate::db::store::Stores;
+
+/// The longest wait between two connection attempts
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Database pool settings, read from the environment
+///
+/// DB_MAX_CONNECTIONS (default 5) and DB_MIN_CONNECTIONS (default 0) bound the pool, DB_ACQUIRE_TIMEOUT (seconds, default 30) is how long a request waits for a connection and DB_IDLE_TIMEOUT (seconds, default 600, 0 to keep them) is how long an unused connection above the minimum is kept. DB_STATEMENT_CACHE_CAPACITY (default 100) is the number of prepared statements kept per connection. On start the server tries to connect DB_CONNECT_ATTEMPTS times (default 5), waiting DB_CONNECT_BACKOFF milliseconds (default 500) after the first failure and twice as long after each one after that
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Option<Duration>,
+    pub statement_cache_capacity: usize,
+    pub connect_attempts: u32,
+    pub connect_backoff: Duration
+}
+
+impl PoolConfig {
+    pub fn from_env() -> Self {
+        let config = Self {
+            max_connections: env_or("DB_MAX_CONNECTIONS", 5),
+            min_connections: env_or("DB_MIN_CONNECTIONS", 0),
+            acquire_timeout: Duration::from_secs(env_or("DB_ACQUIRE_TIMEOUT", 30)),
+            idle_timeout: match env_or("DB_IDLE_TIMEOUT", 600) {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds))
+            },
+            statement_cache_capacity: env_or("DB_STATEMENT_CACHE_CAPACITY", 100),
+            connect_attempts: env_or("DB_CONNECT_ATTEMPTS", 5),
+            connect_backoff: Duration::from_millis(env_or("DB_CONNECT_BACKOFF", 500))
+        };
+
+        if config.max_connections == 0 || config.min_connections > config.max_connections {
+            eprintln!("DB_MAX_CONNECTIONS must be at least 1 and at least DB_MIN_CONNECTIONS in .env");
+            std::process::exit(1)
+        }
+        if config.connect_attempts == 0 {
+            eprintln!("DB_CONNECT_ATTEMPTS must be at least 1 in .env");
+            std::process::exit(1)
+        }
+
+        config
+    }
+
+    /// The pool options for any database
+    pub fn pool_options<DB: Database>(&self) -> PoolOptions<DB> {
+        PoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+    }
+
+    /// How long to wait before the next connection attempt, doubling after every failure up to 30 seconds
+    ///
+    /// # Arguments
+    /// - `failures`: The number of attempts that have failed so far
+    ///
+    /// # Returns
+    /// The time to wait
+    pub fn retry_delay(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.connect_backoff.saturating_mul(factor).min(MAX_CONNECT_BACKOFF)
+    }
+}
+
+/// Connects to a database, retrying with a growing delay so the server can start while the database is still coming up
+///
+/// # Arguments
+/// - `config`: The number of attempts and the delay between them
+/// - `connect`: Makes one connection attempt
+///
+/// # Returns
+/// The pool, or the error from the last attempt
+pub async fn connect_with_retry<DB, F, Fut>(config: &PoolConfig, connect: F) -> Result<Pool<DB>, sqlx::error::Error>
+where
+    DB: Database,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Pool<DB>, sqlx::error::Error>>
+{
+    let mut failures = 0;
+    loop {
+        match connect().await {
+            Ok(pool) => return Ok(pool),
+            Err(e) if failures + 1 < config.connect_attempts => {
+                failures += 1;
+                let delay = config.retry_delay(failures);
+                eprintln!("Failed to connect to the database ({}), attempt {} of {}, retrying in {}ms", e, failures, config.connect_attempts, delay.as_millis());
+                rocket::tokio::time::sleep(delay).await;
+            },
+            Err(e) => return Err(e)
+        }
+    }
+}
+
+/// Connects to the storage backend picked with STORAGE_BACKEND and checks its schema. The process exits if the backend cannot be used, as the server cannot run without it
+///
+/// # Arguments
+/// - `run_migrations`: Whether pending migrations are applied before the schema is checked
+///
+/// # Returns
+/// The stores for the selected backend
+pub async fn connect_stores(run_migrations: bool) -> Stores {
+    match env_or("STORAGE_BACKEND", "postgres".to_string()).as_str() {
+        "postgres" => {
+            let Ok(db_url) = env::var("DATABASE_URL") else {
+                eprintln!("DATABASE_URL is not set in .env");
+                std::process::exit(1)
+            };
+            let config = PoolConfig::from_env();
+            let Ok(options) = PgConnectOptions::from_str(&db_url) else {
+                eprintln!("DATABASE_URL is invalid in .env");
+                std::process::exit(1)
+            };
+            let options = options.statement_cache_capacity(config.statement_cache_capacity);
+
+            let pool = match connect_with_retry(&config, || config.pool_options().connect_with(options.clone())).await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    eprintln!("Failed to connect to the database: {}", e);
+                    std::process::exit(1)
+                }
+            };
+            if let Err(e) = prepare_schema(&POSTGRES_MIGRATOR, &pool, run_migrations).await {
+                eprintln!("Database schema is not compatible: {}", e);
+                std::process::exit(1)
+            }
+            Stores::postgres(pool)
+        },
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            use crate::db::migrations::SQLITE_MIGRATOR;
+            use sqlx::sqlite::SqliteConnectOptions;
+
+            let Ok(db_url) = env::var("SQLITE_DATABASE_URL") else {
+                eprintln!("SQLITE_DATABASE_URL is not set in .env");
+                std::process::exit(1)
+            };
+            let config = PoolConfig::from_env();
+            let Ok(options) = SqliteConnectOptions::from_str(&db_url) else {
+                eprintln!("SQLITE_DATABASE_URL is invalid in .env");
+                std::process::exit(1)
+            };
+            let options = options.create_if_missing(true)
+                .statement_cache_capacity(config.statement_cache_capacity);
+
+            let pool = match connect_with_retry(&config, || config.pool_options().connect_with(options.clone())).await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    eprintln!("Failed to open the database: {}", e);
+                    std::process::exit(1)
+                }
+            };
+            if let Err(e) = prepare_schema(&SQLITE_MIGRATOR, &pool, run_migrations).await {
+                eprintln!("Database schema is not compatible: {}", e);
+                std::process::exit(1)
+            }
+            Stores::sqlite(pool)
+        },
+        "memory" => Stores::memory(),
+        backend => {
+            eprintln!("STORAGE_BACKEND {} is not supported, use postgres, sqlite (with the sqlite feature) or memory", backend);
+            std::process::exit(1)
+        }
+    }
+}
@@ -1,34 +1,46 @@
-use sqlx::PgPool;
+use sqlx::{FromRow, PgPool};
 use rocket::serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+/// A stored refresh token. Every sign-in starts a new session, and each refresh replaces the session's token with a new one; the old entry is kept with `rotated_at` set so that reuse of a stolen token can be detected
+#[derive(FromRow, Deserialize, Serialize, Debug, Clone)]
 pub struct RefreshToken {
+    pub token_id: i64,
     pub user_id: i64,
-    pub refresh_token: String
+    pub session_id: Uuid,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>
 }
 
 /// Creates a refresh token entry is a PostgreSQL database; this function does not check the input
 ///
 /// # Arguments
-/// - `args`: A RefreshToken struct containing the user_id and the refresh token
+/// - `user_id`: The id of the user the token belongs to
+/// - `session_id`: The session the token belongs to, a new one is made on every sign-in
+/// - `token_hash`: The hash of the refresh token
+/// - `expires_at`: When the refresh token stops being accepted
 /// - `pool`: A reference to a PgPool which is required to connect to SQL databases
 ///
 /// # Returns
 /// A Result enum with no data or an sql::error::Error enum if the operation is not successful
-pub async fn create_refresh_entry(args: RefreshToken, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
-    let _ = sqlx::query!(
+#[tracing::instrument(skip_all, fields(db.system = "postgresql", user_id, session_id = %session_id))]
+pub async fn create_refresh_entry(user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
             r#"
-            INSERT INTO refresh_tokens (user_id, refresh_token)
-            VALUES ($1, $2)
-            ON CONFLICT (user_id)
-            DO UPDATE SET
-                refresh_token = $2
+            INSERT INTO refresh_tokens (user_id, session_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
             "#,
-            args.user_id,
-            args.refresh_token,
+            user_id,
+            session_id,
+            token_hash,
+            expires_at,
             )
         .execute(pool)
         .await?;
 
     Ok(())
-}
\ No newline at end of file
+}
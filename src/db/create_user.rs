@@ -8,18 +8,19 @@ use sqlx::PgPool;
 /// - `pool`: A reference to a PgPool which is required to connect to SQL databases
 ///
 /// # Returns
-/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
-pub async fn write_user(user: &UserArgs, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
-    let _ = sqlx::query!(
+/// A Result enum with the user_id of the new user or an sql::error::Error enum if the operation is not successful
+pub async fn write_user(user: &UserArgs, pool: &PgPool) -> Result<i64,  sqlx::error::Error> {
+    let created = sqlx::query!(
             r#"
             INSERT INTO users (username, password)
             VALUES ($1, $2)
+            RETURNING user_id
             "#,
             user.username,
             user.password,
             )
-        .execute(pool)
+        .fetch_one(pool)
         .await?;
 
-    Ok(())
+    Ok(created.user_id)
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+/// Deletes a user along with their refresh tokens and roles
+///
+/// # Arguments
+/// - `user_id`: The id of the user to delete
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with true if the user existed or an sql::error::Error enum if the operation is not successful
+pub async fn delete_user(user_id: i64, pool: &PgPool) -> Result<bool,  sqlx::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE user_id = $1
+            "#,
+            user_id,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    let deleted = sqlx::query!(
+            r#"
+            DELETE FROM users
+            WHERE user_id = $1
+            "#,
+            user_id,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(deleted.rows_affected() > 0)
+}
+
+
+/// Marks a user as deleted and revokes their refresh tokens. The user is no longer found by the other queries, and is removed for good by purge_deleted_users
+///
+/// # Arguments
+/// - `user_id`: The id of the user to delete
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with true if the user existed and was not already deleted or an sql::error::Error enum if the operation is not successful
+pub async fn soft_delete_user(user_id: i64, pool: &PgPool) -> Result<bool,  sqlx::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let deleted = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deleted_at = now()
+            WHERE user_id = $1 AND deleted_at IS NULL
+            "#,
+            user_id,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = now()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(deleted.rows_affected() > 0)
+}
+
+/// Removes the users that were deleted before a point in time along with their refresh tokens and roles
+///
+/// # Arguments
+/// - `deleted_before`: Users deleted before this time are removed
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the number of users removed or an sql::error::Error enum if the operation is not successful
+pub async fn purge_deleted_users(deleted_before: DateTime<Utc>, pool: &PgPool) -> Result<u64,  sqlx::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE user_id IN (SELECT user_id FROM users WHERE deleted_at < $1)
+            "#,
+            deleted_before,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    let purged = sqlx::query!(
+            r#"
+            DELETE FROM users
+            WHERE deleted_at < $1
+            "#,
+            deleted_before,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(purged.rows_affected())
+}
@@ -0,0 +1,26 @@
+use sqlx::PgPool;
+
+/// Disables or re-enables a user. Disabled users cannot sign in
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `disabled`: Whether the user is disabled
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn set_user_disabled(user_id: i64, disabled: bool, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET disabled_at = CASE WHEN $2 THEN COALESCE(disabled_at, now()) ELSE NULL END
+            WHERE user_id = $1
+            "#,
+            user_id,
+            disabled,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
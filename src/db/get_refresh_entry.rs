@@ -1,27 +1,25 @@
 use sqlx::PgPool;
 use crate::db::create_refresh_entry::RefreshToken;
 
-/// Creates a user entry in a PostgreSQL database using SQLx. This function does not check the input
+/// Finds a refresh token entry by the hash of the token using the unique index on token_hash
 ///
 /// # Arguments
-/// - `user`: A reference to a UserArgs struct containing a username and hashed password
+/// - `token_hash`: The hash of the refresh token sent by the client
 /// - `pool`: A reference to a PgPool which is required to connect to SQL databases
 ///
 /// # Returns
-/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
-pub async fn get_refresh_entry(user_id: i64, pool: &PgPool) -> Result<RefreshToken,  sqlx::error::Error> {
-    let tokens = sqlx::query_as!(
+/// A Result enum with the matching RefreshToken if there is one, or an sql::error::Error enum if the operation is not successful
+pub async fn get_refresh_entry(token_hash: &str, pool: &PgPool) -> Result<Option<RefreshToken>,  sqlx::error::Error> {
+    let token = sqlx::query_as!(
             RefreshToken,
             r#"
             SELECT * FROM refresh_tokens
-            WHERE user_id = $1
-            LIMIT 1
+            WHERE token_hash = $1
             "#,
-            user_id
+            token_hash
             )
-        .fetch_all(pool)
+        .fetch_optional(pool)
         .await?;
 
-
-    tokens.get(0).cloned().ok_or(sqlx::error::Error::RowNotFound)
-}
\ No newline at end of file
+    Ok(token)
+}
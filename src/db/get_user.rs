@@ -9,12 +9,13 @@ use crate::db::user::User;
 ///
 /// # Returns
 /// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+#[tracing::instrument(skip_all, fields(db.system = "postgresql"))]
 pub async fn read_user(username: &str, pool: &PgPool) -> Result<Vec<User>,  sqlx::error::Error> {
     let user = sqlx::query_as!(
             User,
             r#"
             SELECT * FROM users
-            WHERE username = $1
+            WHERE username = $1 AND deleted_at IS NULL
             "#,
             username
             )
@@ -22,4 +23,27 @@ pub async fn read_user(username: &str, pool: &PgPool) -> Result<Vec<User>,  sqlx
         .await?;
 
     Ok(user)
-}
\ No newline at end of file
+}
+
+/// Finds a user by their id; deleted users are not returned
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the user if there is one, or an sql::error::Error enum if the operation is not successful
+pub async fn read_user_by_id(user_id: i64, pool: &PgPool) -> Result<Option<User>,  sqlx::error::Error> {
+    let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT * FROM users
+            WHERE user_id = $1 AND deleted_at IS NULL
+            "#,
+            user_id
+            )
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
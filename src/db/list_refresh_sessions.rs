@@ -0,0 +1,50 @@
+use sqlx::PgPool;
+use crate::db::create_refresh_entry::RefreshToken;
+
+/// Finds the active refresh sessions of a user; each session is represented by its current token, which has not been rotated, revoked or expired
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the current token of each session, newest first, or an sql::error::Error enum if the operation is not successful
+pub async fn list_refresh_sessions(user_id: i64, pool: &PgPool) -> Result<Vec<RefreshToken>,  sqlx::error::Error> {
+    let tokens = sqlx::query_as!(
+            RefreshToken,
+            r#"
+            SELECT * FROM refresh_tokens
+            WHERE user_id = $1 AND rotated_at IS NULL AND revoked_at IS NULL AND expires_at > now()
+            ORDER BY created_at DESC
+            "#,
+            user_id
+            )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(tokens)
+}
+
+/// Finds every refresh token stored for a user, including rotated, revoked and expired ones
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the tokens, newest first, or an sql::error::Error enum if the operation is not successful
+pub async fn list_refresh_tokens(user_id: i64, pool: &PgPool) -> Result<Vec<RefreshToken>,  sqlx::error::Error> {
+    let tokens = sqlx::query_as!(
+            RefreshToken,
+            r#"
+            SELECT * FROM refresh_tokens
+            WHERE user_id = $1
+            ORDER BY created_at DESC, token_id DESC
+            "#,
+            user_id
+            )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(tokens)
+}
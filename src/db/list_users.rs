@@ -0,0 +1,53 @@
+use sqlx::PgPool;
+use crate::db::user::User;
+
+/// Finds a page of users ordered by user_id, optionally only those whose username contains a search term, ignoring case
+///
+/// # Arguments
+/// - `search`: Text the username must contain, or None for every user
+/// - `limit`: The maximum number of users returned
+/// - `offset`: The number of matching users skipped
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the users or an sql::error::Error enum if the operation is not successful
+pub async fn list_users(search: Option<&str>, limit: i64, offset: i64, pool: &PgPool) -> Result<Vec<User>,  sqlx::error::Error> {
+    let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT * FROM users
+            WHERE deleted_at IS NULL AND ($1::text IS NULL OR strpos(lower(username), lower($1)) > 0)
+            ORDER BY user_id
+            LIMIT $2 OFFSET $3
+            "#,
+            search,
+            limit,
+            offset
+            )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(users)
+}
+
+/// Counts the users matching a search in the same way as list_users
+///
+/// # Arguments
+/// - `search`: Text the username must contain, or None for every user
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the number of users or an sql::error::Error enum if the operation is not successful
+pub async fn count_users(search: Option<&str>, pool: &PgPool) -> Result<i64,  sqlx::error::Error> {
+    let count = sqlx::query_scalar!(
+            r#"
+            SELECT count(*) AS "count!" FROM users
+            WHERE deleted_at IS NULL AND ($1::text IS NULL OR strpos(lower(username), lower($1)) > 0)
+            "#,
+            search
+            )
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count)
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::auth_events::{AuthEvent, NewAuthEvent};
+use crate::db::create_refresh_entry::RefreshToken;
+use crate::db::store::{EventStore, SessionStore, StoreError, UserStore};
+use crate::db::user::{User, UserArgs};
+
+/// An in-memory store for tests and local development. Nothing is kept once the server stops
+#[derive(Default)]
+pub struct MemoryStore {
+    users: Mutex<Vec<User>>,
+    roles: Mutex<Vec<(i64, String)>>,
+    refresh_tokens: Mutex<Vec<RefreshToken>>,
+    revoked_access_tokens: Mutex<HashMap<Uuid, DateTime<Utc>>>,
+    events: Mutex<Vec<AuthEvent>>,
+    /// The last ids handed out, which only grow like a serial column so ids of deleted rows are never reused
+    last_user_id: AtomicI64,
+    last_token_id: AtomicI64,
+    last_event_id: AtomicI64
+}
+
+#[rocket::async_trait]
+impl UserStore for MemoryStore {
+    async fn write_user(&self, user: &UserArgs) -> Result<i64, StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if users.iter().any(|existing| existing.username == user.username) {
+            return Err(StoreError::Conflict);
+        }
+
+        let user_id = next_id(&self.last_user_id);
+        users.push(User {
+            user_id,
+            username: user.username.clone(),
+            password: user.password.clone(),
+            disabled_at: None,
+            display_name: None,
+            email: None,
+            email_verified_at: None,
+            email_token_hash: None,
+            email_token_expires_at: None,
+            deleted_at: None
+        });
+
+        Ok(user_id)
+    }
+
+    async fn read_user(&self, username: &str) -> Result<Vec<User>, StoreError> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().filter(|user| user.username == username && user.deleted_at.is_none()).cloned().collect())
+    }
+
+    async fn read_user_by_id(&self, user_id: i64) -> Result<Option<User>, StoreError> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|user| user.user_id == user_id && user.deleted_at.is_none()).cloned())
+    }
+
+    async fn list_users(&self, search: Option<&str>, limit: i64, offset: i64) -> Result<Vec<User>, StoreError> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter()
+            .filter(|user| matches_search(user, search))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, StoreError> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().filter(|user| matches_search(user, search)).count() as i64)
+    }
+
+    async fn update_password(&self, user_id: i64, password: &str) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.password = password.to_string();
+        }
+
+        Ok(())
+    }
+
+    async fn update_username(&self, user_id: i64, username: &str) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if users.iter().any(|user| user.username == username && user.user_id != user_id) {
+            return Err(StoreError::Conflict);
+        }
+
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.username = username.to_string();
+        }
+
+        Ok(())
+    }
+
+    async fn set_display_name(&self, user_id: i64, display_name: Option<&str>) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.display_name = display_name.map(str::to_string);
+        }
+
+        Ok(())
+    }
+
+    async fn set_email(&self, user_id: i64, email: Option<&str>, token_hash: Option<&str>, token_expires_at: Option<DateTime<Utc>>) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.email = email.map(str::to_string);
+            user.email_verified_at = None;
+            user.email_token_hash = token_hash.map(str::to_string);
+            user.email_token_expires_at = token_expires_at;
+        }
+
+        Ok(())
+    }
+
+    async fn mark_email_verified(&self, user_id: i64) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.email_verified_at = Some(Utc::now());
+            user.email_token_hash = None;
+            user.email_token_expires_at = None;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        self.refresh_tokens.lock().unwrap().retain(|token| token.user_id != user_id);
+        self.roles.lock().unwrap().retain(|(role_user_id, _)| *role_user_id != user_id);
+        self.events.lock().unwrap().retain(|event| event.user_id != Some(user_id));
+
+        let mut users = self.users.lock().unwrap();
+        let count = users.len();
+        users.retain(|user| user.user_id != user_id);
+
+        Ok(users.len() < count)
+    }
+
+    async fn soft_delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        let now = Utc::now();
+        self.refresh_tokens.lock().unwrap().iter_mut()
+            .filter(|token| token.user_id == user_id && token.revoked_at.is_none())
+            .for_each(|token| token.revoked_at = Some(now));
+
+        let mut users = self.users.lock().unwrap();
+        match users.iter_mut().find(|user| user.user_id == user_id && user.deleted_at.is_none()) {
+            Some(user) => {
+                user.deleted_at = Some(now);
+                Ok(true)
+            },
+            None => Ok(false)
+        }
+    }
+
+    async fn purge_deleted_users(&self, deleted_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        let mut users = self.users.lock().unwrap();
+        let purged: Vec<i64> = users.iter()
+            .filter(|user| user.deleted_at.is_some_and(|deleted_at| deleted_at < deleted_before))
+            .map(|user| user.user_id)
+            .collect();
+
+        users.retain(|user| !purged.contains(&user.user_id));
+        self.refresh_tokens.lock().unwrap().retain(|token| !purged.contains(&token.user_id));
+        self.roles.lock().unwrap().retain(|(role_user_id, _)| !purged.contains(role_user_id));
+        self.events.lock().unwrap().retain(|event| !event.user_id.is_some_and(|user_id| purged.contains(&user_id)));
+
+        Ok(purged.len() as u64)
+    }
+
+    async fn set_user_disabled(&self, user_id: i64, disabled: bool) -> Result<(), StoreError> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(user) = users.iter_mut().find(|user| user.user_id == user_id) {
+            user.disabled_at = match disabled {
+                true => user.disabled_at.or(Some(Utc::now())),
+                false => None
+            };
+        }
+
+        Ok(())
+    }
+
+    async fn get_roles(&self, user_id: i64) -> Result<Vec<String>, StoreError> {
+        let roles = self.roles.lock().unwrap();
+        let mut user_roles: Vec<String> = roles.iter()
+            .filter(|(role_user_id, _)| *role_user_id == user_id)
+            .map(|(_, role)| role.clone())
+            .collect();
+        user_roles.sort();
+
+        Ok(user_roles)
+    }
+
+    async fn add_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        let mut roles = self.roles.lock().unwrap();
+        if !roles.iter().any(|(role_user_id, existing)| *role_user_id == user_id && existing == role) {
+            roles.push((user_id, role.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn remove_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        self.roles.lock().unwrap().retain(|(role_user_id, existing)| !(*role_user_id == user_id && existing == role));
+        Ok(())
+    }
+}
+
+#[rocket::async_trait]
+impl SessionStore for MemoryStore {
+    async fn create_refresh_entry(&self, user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+        insert_token(&mut refresh_tokens, &self.last_token_id, user_id, session_id, token_hash, expires_at)
+    }
+
+    async fn get_refresh_entry(&self, token_hash: &str) -> Result<Option<RefreshToken>, StoreError> {
+        let refresh_tokens = self.refresh_tokens.lock().unwrap();
+        Ok(refresh_tokens.iter().find(|token| token.token_hash == token_hash).cloned())
+    }
+
+    async fn rotate_refresh_entry(&self, old_token: &RefreshToken, new_token_hash: &str, new_expires_at: DateTime<Utc>) -> Result<bool, StoreError> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+
+        let Some(token) = refresh_tokens.iter_mut()
+            .find(|token| token.token_id == old_token.token_id && token.rotated_at.is_none() && token.revoked_at.is_none()) else {
+            return Ok(false);
+        };
+        token.rotated_at = Some(Utc::now());
+
+        insert_token(&mut refresh_tokens, &self.last_token_id, old_token.user_id, old_token.session_id, new_token_hash, new_expires_at)?;
+        Ok(true)
+    }
+
+    async fn revoke_refresh_session(&self, session_id: Uuid) -> Result<(), StoreError> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+        let now = Utc::now();
+
+        refresh_tokens.iter_mut()
+            .filter(|token| token.session_id == session_id && token.revoked_at.is_none())
+            .for_each(|token| token.revoked_at = Some(now));
+
+        Ok(())
+    }
+
+    async fn list_refresh_sessions(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        let refresh_tokens = self.refresh_tokens.lock().unwrap();
+        let now = Utc::now();
+
+        let mut sessions: Vec<RefreshToken> = refresh_tokens.iter()
+            .filter(|token| token.user_id == user_id && token.rotated_at.is_none() && token.revoked_at.is_none() && token.expires_at > now)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|token| std::cmp::Reverse(token.created_at));
+
+        Ok(sessions)
+    }
+
+    async fn list_refresh_tokens(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        let refresh_tokens = self.refresh_tokens.lock().unwrap();
+
+        let mut tokens: Vec<RefreshToken> = refresh_tokens.iter()
+            .filter(|token| token.user_id == user_id)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| std::cmp::Reverse((token.created_at, token.token_id)));
+
+        Ok(tokens)
+    }
+
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<(), StoreError> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+        let now = Utc::now();
+
+        refresh_tokens.iter_mut()
+            .filter(|token| token.user_id == user_id && token.revoked_at.is_none())
+            .for_each(|token| token.revoked_at = Some(now));
+
+        Ok(())
+    }
+
+    async fn revoke_all_sessions(&self) -> Result<(), StoreError> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+        let now = Utc::now();
+
+        refresh_tokens.iter_mut()
+            .filter(|token| token.revoked_at.is_none())
+            .for_each(|token| token.revoked_at = Some(now));
+
+        Ok(())
+    }
+
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        self.revoked_access_tokens.lock().unwrap().entry(jti).or_insert(expires_at);
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool, StoreError> {
+        Ok(self.revoked_access_tokens.lock().unwrap().contains_key(&jti))
+    }
+
+    async fn purge_revoked_access_tokens(&self, expired_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        let mut revoked_access_tokens = self.revoked_access_tokens.lock().unwrap();
+        let count = revoked_access_tokens.len();
+        revoked_access_tokens.retain(|_, expires_at| *expires_at >= expired_before);
+
+        Ok((count - revoked_access_tokens.len()) as u64)
+    }
+}
+
+#[rocket::async_trait]
+impl EventStore for MemoryStore {
+    async fn record_event(&self, event: &NewAuthEvent) -> Result<(), StoreError> {
+        let mut events = self.events.lock().unwrap();
+        let event_id = next_id(&self.last_event_id);
+        events.push(AuthEvent {
+            event_id,
+            user_id: event.user_id,
+            event_type: event.event_type.as_str().to_string(),
+            ip_address: event.ip_address.clone(),
+            user_agent: event.user_agent.clone(),
+            created_at: Utc::now()
+        });
+
+        Ok(())
+    }
+
+    async fn list_events(&self, user_id: Option<i64>, limit: i64, offset: i64) -> Result<Vec<AuthEvent>, StoreError> {
+        let events = self.events.lock().unwrap();
+        Ok(events.iter()
+            .rev()
+            .filter(|event| user_id.is_none() || event.user_id == user_id)
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn count_events(&self, user_id: Option<i64>) -> Result<i64, StoreError> {
+        let events = self.events.lock().unwrap();
+        Ok(events.iter().filter(|event| user_id.is_none() || event.user_id == user_id).count() as i64)
+    }
+}
+
+fn matches_search(user: &User, search: Option<&str>) -> bool {
+    user.deleted_at.is_none() && search.is_none_or(|search| user.username.to_lowercase().contains(&search.to_lowercase()))
+}
+
+fn next_id(last_id: &AtomicI64) -> i64 {
+    last_id.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+fn insert_token(refresh_tokens: &mut Vec<RefreshToken>, last_token_id: &AtomicI64, user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+    if refresh_tokens.iter().any(|token| token.token_hash == token_hash) {
+        return Err(StoreError::Conflict);
+    }
+
+    let token_id = next_id(last_token_id);
+    refresh_tokens.push(RefreshToken {
+        token_id,
+        user_id,
+        session_id,
+        token_hash: token_hash.to_string(),
+        created_at: Utc::now(),
+        expires_at,
+        rotated_at: None,
+        revoked_at: None
+    });
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use std::future::Future;
+
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::postgres::PgConnection;
+use sqlx::{Connection, Database, Pool, Postgres};
+
+/// The PostgreSQL migrations in migrations/, embedded at compile time
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// The SQLite migrations in migrations/sqlite/, embedded at compile time
+#[cfg(feature = "sqlite")]
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Reasons the database schema does not match the migrations this server was built with
+#[derive(Debug)]
+pub enum SchemaError {
+    /// A migration has not been applied yet
+    Pending(i64),
+    /// The database has a migration this server does not know about, usually because it was migrated by a newer version
+    Unknown(i64),
+    /// An applied migration was changed after it was applied
+    Modified(i64),
+    /// A migration failed part way through
+    Dirty(i64),
+    /// The database still has the refresh_tokens table created by database_setup.sql, which the first migration cannot adopt
+    PreMigrationSchema,
+    Migrate(MigrateError)
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending(version) => write!(f, "migration {} has not been applied, run the migrate command or set RUN_MIGRATIONS=true", version),
+            Self::Unknown(version) => write!(f, "the database has migration {} which this server does not know about", version),
+            Self::Modified(version) => write!(f, "migration {} was changed after it was applied", version),
+            Self::Dirty(version) => write!(f, "migration {} was only partially applied", version),
+            Self::PreMigrationSchema => write!(f, "the database has the refresh_tokens table created by database_setup.sql, which stored one plain refresh token per user. Drop it with 'DROP TABLE refresh_tokens;', which signs every user out, and run the migrations again"),
+            Self::Migrate(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<MigrateError> for SchemaError {
+    fn from(e: MigrateError) -> Self {
+        match e {
+            MigrateError::VersionMissing(version) => Self::Unknown(version),
+            MigrateError::VersionMismatch(version) => Self::Modified(version),
+            MigrateError::Dirty(version) => Self::Dirty(version),
+            e => Self::Migrate(e)
+        }
+    }
+}
+
+impl From<sqlx::error::Error> for SchemaError {
+    fn from(e: sqlx::error::Error) -> Self {
+        Self::Migrate(MigrateError::Execute(e))
+    }
+}
+
+/// Compares the migrations applied to a database with the ones embedded in the server without changing the schema
+///
+/// # Arguments
+/// - `migrator`: The embedded migrations
+/// - `conn`: A connection to the database
+///
+/// # Returns
+/// An error describing the first difference found
+pub async fn check_schema<C: Migrate + ?Sized>(migrator: &Migrator, conn: &mut C) -> Result<(), SchemaError> {
+    conn.ensure_migrations_table().await?;
+
+    if let Some(version) = conn.dirty_version().await? {
+        return Err(SchemaError::Dirty(version));
+    }
+
+    let applied = conn.list_applied_migrations().await?;
+    for applied_migration in &applied {
+        let known = migrator.iter()
+            .find(|migration| migration.version == applied_migration.version && !migration.migration_type.is_down_migration());
+
+        match known {
+            None => return Err(SchemaError::Unknown(applied_migration.version)),
+            Some(migration) if migration.checksum != applied_migration.checksum => {
+                return Err(SchemaError::Modified(applied_migration.version))
+            },
+            Some(_) => {}
+        }
+    }
+
+    let pending = migrator.iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .find(|migration| !applied.iter().any(|applied_migration| applied_migration.version == migration.version));
+
+    match pending {
+        Some(migration) => Err(SchemaError::Pending(migration.version)),
+        None => Ok(())
+    }
+}
+
+/// Checks that a database can be reached by taking a connection from the pool and pinging it
+///
+/// # Arguments
+/// - `pool`: The database pool
+///
+/// # Returns
+/// Nothing, or the sqlx::error::Error from connecting
+pub async fn ping<DB: Database>(pool: &Pool<DB>) -> Result<(), sqlx::error::Error> {
+    let mut conn = pool.acquire().await?;
+    conn.ping().await
+}
+
+/// Optionally applies the pending migrations and then checks that the schema matches the server, which refuses to start otherwise
+///
+/// # Arguments
+/// - `migrator`: The embedded migrations
+/// - `pool`: The database pool
+/// - `run_migrations`: Whether pending migrations are applied first
+pub async fn prepare_schema<DB>(migrator: &Migrator, pool: &Pool<DB>, run_migrations: bool) -> Result<(), SchemaError>
+where
+    DB: PreMigrationSchema,
+    DB::Connection: Migrate
+{
+    {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        if conn.list_applied_migrations().await?.is_empty() && DB::has_pre_migration_schema(&mut conn).await? {
+            return Err(SchemaError::PreMigrationSchema);
+        }
+    }
+
+    if run_migrations {
+        migrator.run(pool).await?;
+    }
+
+    let mut conn = pool.acquire().await?;
+    check_schema(migrator, &mut *conn).await
+}
+
+/// Databases that can be checked for the schema database_setup.sql created before the migrations
+pub trait PreMigrationSchema: Database {
+    /// Checks for the refresh_tokens table database_setup.sql created, which has a refresh_token column rather than the token_hash of the migrated schema. The first migration only creates tables that do not exist, so it would keep this one
+    ///
+    /// # Arguments
+    /// - `conn`: A connection to a database no migrations have been applied to
+    ///
+    /// # Returns
+    /// True if the old table is there
+    fn has_pre_migration_schema(conn: &mut Self::Connection) -> impl Future<Output = Result<bool, sqlx::error::Error>> + Send + '_;
+}
+
+impl PreMigrationSchema for Postgres {
+    fn has_pre_migration_schema(conn: &mut PgConnection) -> impl Future<Output = Result<bool, sqlx::error::Error>> + Send + '_ {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = 'refresh_tokens' AND column_name = 'refresh_token')"
+        ).fetch_one(conn)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl PreMigrationSchema for sqlx::Sqlite {
+    fn has_pre_migration_schema(conn: &mut sqlx::SqliteConnection) -> impl Future<Output = Result<bool, sqlx::error::Error>> + Send + '_ {
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pragma_table_info('refresh_tokens') WHERE name = 'refresh_token')")
+            .fetch_one(conn)
+    }
+}
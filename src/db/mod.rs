@@ -1,5 +1,23 @@
 pub mod user;
 pub mod create_user;
 pub mod get_user;
+pub mod update_user;
+pub mod update_profile;
 pub mod create_refresh_entry;
-pub mod get_refresh_entry;
\ No newline at end of file
+pub mod get_refresh_entry;
+pub mod rotate_refresh_entry;
+pub mod revoke_refresh_entry;
+pub mod revoked_access_tokens;
+pub mod delete_user;
+pub mod disable_user;
+pub mod user_roles;
+pub mod list_refresh_sessions;
+pub mod list_users;
+pub mod auth_events;
+pub mod store;
+pub mod postgres_store;
+pub mod memory_store;
+pub mod migrations;
+pub mod connect;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_store;
\ No newline at end of file
@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::auth_events::{count_events, list_events, record_event, AuthEvent, NewAuthEvent};
+use crate::db::create_refresh_entry::{create_refresh_entry, RefreshToken};
+use crate::db::create_user::write_user;
+use crate::db::delete_user::{delete_user, purge_deleted_users, soft_delete_user};
+use crate::db::disable_user::set_user_disabled;
+use crate::db::get_refresh_entry::get_refresh_entry;
+use crate::db::get_user::{read_user, read_user_by_id};
+use crate::db::list_refresh_sessions::{list_refresh_sessions, list_refresh_tokens};
+use crate::db::list_users::{count_users, list_users};
+use crate::db::migrations::{check_schema, ping, SchemaError, POSTGRES_MIGRATOR};
+use crate::db::revoke_refresh_entry::{revoke_all_sessions, revoke_refresh_session, revoke_user_sessions};
+use crate::db::revoked_access_tokens::{is_access_token_revoked, purge_revoked_access_tokens, revoke_access_token};
+use crate::db::rotate_refresh_entry::rotate_refresh_entry;
+use crate::db::store::{EventStore, PoolStatus, SessionStore, StoreError, UserStore};
+use crate::db::update_profile::{mark_email_verified, set_display_name, set_email, update_username};
+use crate::db::update_user::update_password;
+use crate::db::user::{User, UserArgs};
+use crate::db::user_roles::{add_role, get_roles, remove_role};
+use crate::metrics::collectors::timed;
+
+/// The PostgreSQL store, using the queries in the other db modules. Every query is timed for the auth_db_query_duration_seconds metric
+pub struct PgStore {
+    pool: PgPool
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+#[rocket::async_trait]
+impl UserStore for PgStore {
+    async fn write_user(&self, user: &UserArgs) -> Result<i64, StoreError> {
+        Ok(timed("write_user", write_user(user, &self.pool)).await?)
+    }
+
+    async fn read_user(&self, username: &str) -> Result<Vec<User>, StoreError> {
+        Ok(timed("read_user", read_user(username, &self.pool)).await?)
+    }
+
+    async fn read_user_by_id(&self, user_id: i64) -> Result<Option<User>, StoreError> {
+        Ok(timed("read_user_by_id", read_user_by_id(user_id, &self.pool)).await?)
+    }
+
+    async fn list_users(&self, search: Option<&str>, limit: i64, offset: i64) -> Result<Vec<User>, StoreError> {
+        Ok(timed("list_users", list_users(search, limit, offset, &self.pool)).await?)
+    }
+
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, StoreError> {
+        Ok(timed("count_users", count_users(search, &self.pool)).await?)
+    }
+
+    async fn update_password(&self, user_id: i64, password: &str) -> Result<(), StoreError> {
+        Ok(timed("update_password", update_password(user_id, password, &self.pool)).await?)
+    }
+
+    async fn update_username(&self, user_id: i64, username: &str) -> Result<(), StoreError> {
+        Ok(timed("update_username", update_username(user_id, username, &self.pool)).await?)
+    }
+
+    async fn set_display_name(&self, user_id: i64, display_name: Option<&str>) -> Result<(), StoreError> {
+        Ok(timed("set_display_name", set_display_name(user_id, display_name, &self.pool)).await?)
+    }
+
+    async fn set_email(&self, user_id: i64, email: Option<&str>, token_hash: Option<&str>, token_expires_at: Option<DateTime<Utc>>) -> Result<(), StoreError> {
+        Ok(timed("set_email", set_email(user_id, email, token_hash, token_expires_at, &self.pool)).await?)
+    }
+
+    async fn mark_email_verified(&self, user_id: i64) -> Result<(), StoreError> {
+        Ok(timed("mark_email_verified", mark_email_verified(user_id, &self.pool)).await?)
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        Ok(timed("delete_user", delete_user(user_id, &self.pool)).await?)
+    }
+
+    async fn soft_delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        Ok(timed("soft_delete_user", soft_delete_user(user_id, &self.pool)).await?)
+    }
+
+    async fn purge_deleted_users(&self, deleted_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        Ok(timed("purge_deleted_users", purge_deleted_users(deleted_before, &self.pool)).await?)
+    }
+
+    async fn set_user_disabled(&self, user_id: i64, disabled: bool) -> Result<(), StoreError> {
+        Ok(timed("set_user_disabled", set_user_disabled(user_id, disabled, &self.pool)).await?)
+    }
+
+    async fn get_roles(&self, user_id: i64) -> Result<Vec<String>, StoreError> {
+        Ok(timed("get_roles", get_roles(user_id, &self.pool)).await?)
+    }
+
+    async fn add_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        Ok(timed("add_role", add_role(user_id, role, &self.pool)).await?)
+    }
+
+    async fn remove_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        Ok(timed("remove_role", remove_role(user_id, role, &self.pool)).await?)
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max: self.pool.options().get_max_connections()
+        })
+    }
+
+    async fn ping(&self) -> Result<(), StoreError> {
+        Ok(ping(&self.pool).await?)
+    }
+
+    async fn check_schema(&self) -> Result<(), SchemaError> {
+        let mut conn = self.pool.acquire().await?;
+        check_schema(&POSTGRES_MIGRATOR, &mut *conn).await
+    }
+
+    async fn close(&self) {
+        self.pool.close().await
+    }
+}
+
+#[rocket::async_trait]
+impl SessionStore for PgStore {
+    async fn create_refresh_entry(&self, user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        Ok(timed("create_refresh_entry", create_refresh_entry(user_id, session_id, token_hash, expires_at, &self.pool)).await?)
+    }
+
+    async fn get_refresh_entry(&self, token_hash: &str) -> Result<Option<RefreshToken>, StoreError> {
+        Ok(timed("get_refresh_entry", get_refresh_entry(token_hash, &self.pool)).await?)
+    }
+
+    async fn rotate_refresh_entry(&self, old_token: &RefreshToken, new_token_hash: &str, new_expires_at: DateTime<Utc>) -> Result<bool, StoreError> {
+        Ok(timed("rotate_refresh_entry", rotate_refresh_entry(old_token, new_token_hash, new_expires_at, &self.pool)).await?)
+    }
+
+    async fn revoke_refresh_session(&self, session_id: Uuid) -> Result<(), StoreError> {
+        Ok(timed("revoke_refresh_session", revoke_refresh_session(session_id, &self.pool)).await?)
+    }
+
+    async fn list_refresh_sessions(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        Ok(timed("list_refresh_sessions", list_refresh_sessions(user_id, &self.pool)).await?)
+    }
+
+    async fn list_refresh_tokens(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        Ok(timed("list_refresh_tokens", list_refresh_tokens(user_id, &self.pool)).await?)
+    }
+
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<(), StoreError> {
+        Ok(timed("revoke_user_sessions", revoke_user_sessions(user_id, &self.pool)).await?)
+    }
+
+    async fn revoke_all_sessions(&self) -> Result<(), StoreError> {
+        Ok(timed("revoke_all_sessions", revoke_all_sessions(&self.pool)).await?)
+    }
+
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        Ok(timed("revoke_access_token", revoke_access_token(jti, expires_at, &self.pool)).await?)
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool, StoreError> {
+        Ok(timed("is_access_token_revoked", is_access_token_revoked(jti, &self.pool)).await?)
+    }
+
+    async fn purge_revoked_access_tokens(&self, expired_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        Ok(timed("purge_revoked_access_tokens", purge_revoked_access_tokens(expired_before, &self.pool)).await?)
+    }
+}
+
+#[rocket::async_trait]
+impl EventStore for PgStore {
+    async fn record_event(&self, event: &NewAuthEvent) -> Result<(), StoreError> {
+        Ok(timed("record_event", record_event(event, &self.pool)).await?)
+    }
+
+    async fn list_events(&self, user_id: Option<i64>, limit: i64, offset: i64) -> Result<Vec<AuthEvent>, StoreError> {
+        Ok(timed("list_events", list_events(user_id, limit, offset, &self.pool)).await?)
+    }
+
+    async fn count_events(&self, user_id: Option<i64>) -> Result<i64, StoreError> {
+        Ok(timed("count_events", count_events(user_id, &self.pool)).await?)
+    }
+}
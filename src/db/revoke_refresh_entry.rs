@@ -0,0 +1,69 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Revokes every refresh token in a session, signing that session out once its current JWT expires
+///
+/// # Arguments
+/// - `session_id`: The session to revoke
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn revoke_refresh_session(session_id: Uuid, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = now()
+            WHERE session_id = $1 AND revoked_at IS NULL
+            "#,
+            session_id,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Revokes every refresh session of a user
+///
+/// # Arguments
+/// - `user_id`: The user whose sessions are revoked
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn revoke_user_sessions(user_id: i64, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = now()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Revokes every refresh session of every user, used when the signing keys are replaced
+///
+/// # Arguments
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn revoke_all_sessions(pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = now()
+            WHERE revoked_at IS NULL
+            "#,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Adds a JWT to the denylist so it is refused before it expires. Entries are kept until the token would have expired anyway
+///
+/// # Arguments
+/// - `jti`: The jti claim of the JWT
+/// - `expires_at`: When the JWT expires
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn revoke_access_token(jti: Uuid, expires_at: DateTime<Utc>, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            INSERT INTO revoked_access_tokens (jti, expires_at)
+            VALUES ($1, $2)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            expires_at
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Checks whether a JWT is on the denylist
+///
+/// # Arguments
+/// - `jti`: The jti claim of the JWT
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with true if the JWT has been revoked, or an sql::error::Error enum if the operation is not successful
+pub async fn is_access_token_revoked(jti: Uuid, pool: &PgPool) -> Result<bool,  sqlx::error::Error> {
+    let revoked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM revoked_access_tokens WHERE jti = $1) AS "revoked!"
+            "#,
+            jti
+            )
+        .fetch_one(pool)
+        .await?;
+
+    Ok(revoked)
+}
+
+/// Removes the denylist entries of JWTs that have expired, as they are refused without them
+///
+/// # Arguments
+/// - `expired_before`: Entries for JWTs expiring before this time are removed
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the number of entries removed, or an sql::error::Error enum if the operation is not successful
+pub async fn purge_revoked_access_tokens(expired_before: DateTime<Utc>, pool: &PgPool) -> Result<u64,  sqlx::error::Error> {
+    let result = sqlx::query!(
+            r#"
+            DELETE FROM revoked_access_tokens
+            WHERE expires_at < $1
+            "#,
+            expired_before
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::db::create_refresh_entry::RefreshToken;
+
+/// Replaces a refresh token with a new one in the same session. The old entry is marked as rotated rather than deleted so a later reuse of it can be detected
+///
+/// # Arguments
+/// - `old_token`: The RefreshToken entry that was presented by the client
+/// - `new_token_hash`: The hash of the refresh token replacing it
+/// - `new_expires_at`: When the refresh token replacing it stops being accepted
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with true if the token was rotated, false if it had already been rotated or revoked by another request, or an sql::error::Error enum if the operation is not successful
+pub async fn rotate_refresh_entry(old_token: &RefreshToken, new_token_hash: &str, new_expires_at: DateTime<Utc>, pool: &PgPool) -> Result<bool,  sqlx::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let rotated = sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET rotated_at = now()
+            WHERE token_id = $1 AND rotated_at IS NULL AND revoked_at IS NULL
+            "#,
+            old_token.token_id,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    if rotated.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+            r#"
+            INSERT INTO refresh_tokens (user_id, session_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            old_token.user_id,
+            old_token.session_id,
+            new_token_hash,
+            new_expires_at,
+            )
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(true)
+}
@@ -0,0 +1,436 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::auth_events::{AuthEvent, NewAuthEvent};
+use crate::db::create_refresh_entry::RefreshToken;
+use crate::db::migrations::{check_schema, ping, SchemaError, SQLITE_MIGRATOR};
+use crate::db::store::{EventStore, PoolStatus, SessionStore, StoreError, UserStore};
+use crate::db::user::{User, UserArgs};
+use crate::metrics::collectors::timed;
+
+/// A SQLite store for running the server without a database server, enabled with the sqlite cargo feature
+pub struct SqliteStore {
+    pool: SqlitePool
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[rocket::async_trait]
+impl UserStore for SqliteStore {
+    async fn write_user(&self, user: &UserArgs) -> Result<i64, StoreError> {
+        let user_id = timed("write_user", sqlx::query_scalar("INSERT INTO users (username, password) VALUES ($1, $2) RETURNING user_id")
+            .bind(&user.username)
+            .bind(&user.password)
+            .fetch_one(&self.pool))
+            .await?;
+
+        Ok(user_id)
+    }
+
+    async fn read_user(&self, username: &str) -> Result<Vec<User>, StoreError> {
+        let users = timed("read_user", sqlx::query_as("SELECT * FROM users WHERE username = $1 AND deleted_at IS NULL")
+            .bind(username)
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(users)
+    }
+
+    async fn read_user_by_id(&self, user_id: i64) -> Result<Option<User>, StoreError> {
+        let user = timed("read_user_by_id", sqlx::query_as("SELECT * FROM users WHERE user_id = $1 AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_optional(&self.pool))
+            .await?;
+
+        Ok(user)
+    }
+
+    async fn list_users(&self, search: Option<&str>, limit: i64, offset: i64) -> Result<Vec<User>, StoreError> {
+        let users = timed("list_users", sqlx::query_as("SELECT * FROM users WHERE deleted_at IS NULL AND ($1 IS NULL OR instr(lower(username), lower($1)) > 0) ORDER BY user_id LIMIT $2 OFFSET $3")
+            .bind(search)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(users)
+    }
+
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, StoreError> {
+        let count = timed("count_users", sqlx::query_scalar("SELECT count(*) FROM users WHERE deleted_at IS NULL AND ($1 IS NULL OR instr(lower(username), lower($1)) > 0)")
+            .bind(search)
+            .fetch_one(&self.pool))
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn update_password(&self, user_id: i64, password: &str) -> Result<(), StoreError> {
+        timed("update_password", sqlx::query("UPDATE users SET password = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(password)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_username(&self, user_id: i64, username: &str) -> Result<(), StoreError> {
+        timed("update_username", sqlx::query("UPDATE users SET username = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(username)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_display_name(&self, user_id: i64, display_name: Option<&str>) -> Result<(), StoreError> {
+        timed("set_display_name", sqlx::query("UPDATE users SET display_name = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(display_name)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_email(&self, user_id: i64, email: Option<&str>, token_hash: Option<&str>, token_expires_at: Option<DateTime<Utc>>) -> Result<(), StoreError> {
+        timed("set_email", sqlx::query("UPDATE users SET email = $2, email_verified_at = NULL, email_token_hash = $3, email_token_expires_at = $4 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(email)
+            .bind(token_hash)
+            .bind(token_expires_at)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn mark_email_verified(&self, user_id: i64) -> Result<(), StoreError> {
+        timed("mark_email_verified", sqlx::query("UPDATE users SET email_verified_at = $2, email_token_hash = NULL, email_token_expires_at = NULL WHERE user_id = $1")
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        timed("delete_user", async {
+            let mut transaction = self.pool.begin().await?;
+
+            sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("DELETE FROM user_roles WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("DELETE FROM auth_events WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *transaction)
+                .await?;
+
+            let deleted = sqlx::query("DELETE FROM users WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.commit().await?;
+
+            Ok(deleted.rows_affected() > 0)
+        }).await
+    }
+
+    async fn soft_delete_user(&self, user_id: i64) -> Result<bool, StoreError> {
+        timed("soft_delete_user", async {
+            let mut transaction = self.pool.begin().await?;
+            let now = Utc::now();
+
+            let deleted = sqlx::query("UPDATE users SET deleted_at = $2 WHERE user_id = $1 AND deleted_at IS NULL")
+                .bind(user_id)
+                .bind(now)
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("UPDATE refresh_tokens SET revoked_at = $2 WHERE user_id = $1 AND revoked_at IS NULL")
+                .bind(user_id)
+                .bind(now)
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.commit().await?;
+
+            Ok(deleted.rows_affected() > 0)
+        }).await
+    }
+
+    async fn purge_deleted_users(&self, deleted_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        timed("purge_deleted_users", async {
+            let mut transaction = self.pool.begin().await?;
+
+            sqlx::query("DELETE FROM refresh_tokens WHERE user_id IN (SELECT user_id FROM users WHERE deleted_at < $1)")
+                .bind(deleted_before)
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("DELETE FROM user_roles WHERE user_id IN (SELECT user_id FROM users WHERE deleted_at < $1)")
+                .bind(deleted_before)
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("DELETE FROM auth_events WHERE user_id IN (SELECT user_id FROM users WHERE deleted_at < $1)")
+                .bind(deleted_before)
+                .execute(&mut *transaction)
+                .await?;
+
+            let purged = sqlx::query("DELETE FROM users WHERE deleted_at < $1")
+                .bind(deleted_before)
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.commit().await?;
+
+            Ok(purged.rows_affected())
+        }).await
+    }
+
+    async fn set_user_disabled(&self, user_id: i64, disabled: bool) -> Result<(), StoreError> {
+        let disabled_at = disabled.then(Utc::now);
+        timed("set_user_disabled", sqlx::query("UPDATE users SET disabled_at = CASE WHEN $2 IS NULL THEN NULL ELSE COALESCE(disabled_at, $2) END WHERE user_id = $1")
+            .bind(user_id)
+            .bind(disabled_at)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_roles(&self, user_id: i64) -> Result<Vec<String>, StoreError> {
+        let roles = timed("get_roles", sqlx::query_scalar("SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role")
+            .bind(user_id)
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(roles)
+    }
+
+    async fn add_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        timed("add_role", sqlx::query("INSERT INTO user_roles (user_id, role) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(user_id)
+            .bind(role)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn remove_role(&self, user_id: i64, role: &str) -> Result<(), StoreError> {
+        timed("remove_role", sqlx::query("DELETE FROM user_roles WHERE user_id = $1 AND role = $2")
+            .bind(user_id)
+            .bind(role)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max: self.pool.options().get_max_connections()
+        })
+    }
+
+    async fn ping(&self) -> Result<(), StoreError> {
+        Ok(ping(&self.pool).await?)
+    }
+
+    async fn check_schema(&self) -> Result<(), SchemaError> {
+        let mut conn = self.pool.acquire().await?;
+        check_schema(&SQLITE_MIGRATOR, &mut *conn).await
+    }
+
+    async fn close(&self) {
+        self.pool.close().await
+    }
+}
+
+#[rocket::async_trait]
+impl SessionStore for SqliteStore {
+    async fn create_refresh_entry(&self, user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        timed("create_refresh_entry", sqlx::query("INSERT INTO refresh_tokens (user_id, session_id, token_hash, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)")
+            .bind(user_id)
+            .bind(session_id)
+            .bind(token_hash)
+            .bind(Utc::now())
+            .bind(expires_at)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_refresh_entry(&self, token_hash: &str) -> Result<Option<RefreshToken>, StoreError> {
+        let token = timed("get_refresh_entry", sqlx::query_as("SELECT * FROM refresh_tokens WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool))
+            .await?;
+
+        Ok(token)
+    }
+
+    async fn rotate_refresh_entry(&self, old_token: &RefreshToken, new_token_hash: &str, new_expires_at: DateTime<Utc>) -> Result<bool, StoreError> {
+        timed("rotate_refresh_entry", async {
+            let mut transaction = self.pool.begin().await?;
+            let now = Utc::now();
+
+            let rotated = sqlx::query("UPDATE refresh_tokens SET rotated_at = $2 WHERE token_id = $1 AND rotated_at IS NULL AND revoked_at IS NULL")
+                .bind(old_token.token_id)
+                .bind(now)
+                .execute(&mut *transaction)
+                .await?;
+
+            if rotated.rows_affected() == 0 {
+                return Ok(false);
+            }
+
+            sqlx::query("INSERT INTO refresh_tokens (user_id, session_id, token_hash, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)")
+                .bind(old_token.user_id)
+                .bind(old_token.session_id)
+                .bind(new_token_hash)
+                .bind(now)
+                .bind(new_expires_at)
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.commit().await?;
+
+            Ok(true)
+        }).await
+    }
+
+    async fn revoke_refresh_session(&self, session_id: Uuid) -> Result<(), StoreError> {
+        timed("revoke_refresh_session", sqlx::query("UPDATE refresh_tokens SET revoked_at = $2 WHERE session_id = $1 AND revoked_at IS NULL")
+            .bind(session_id)
+            .bind(Utc::now())
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_refresh_sessions(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        let tokens = timed("list_refresh_sessions", sqlx::query_as("SELECT * FROM refresh_tokens WHERE user_id = $1 AND rotated_at IS NULL AND revoked_at IS NULL AND expires_at > $2 ORDER BY created_at DESC")
+            .bind(user_id)
+            .bind(Utc::now())
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(tokens)
+    }
+
+    async fn list_refresh_tokens(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError> {
+        let tokens = timed("list_refresh_tokens", sqlx::query_as("SELECT * FROM refresh_tokens WHERE user_id = $1 ORDER BY created_at DESC, token_id DESC")
+            .bind(user_id)
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(tokens)
+    }
+
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<(), StoreError> {
+        timed("revoke_user_sessions", sqlx::query("UPDATE refresh_tokens SET revoked_at = $2 WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .bind(Utc::now())
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_all_sessions(&self) -> Result<(), StoreError> {
+        timed("revoke_all_sessions", sqlx::query("UPDATE refresh_tokens SET revoked_at = $1 WHERE revoked_at IS NULL")
+            .bind(Utc::now())
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<(), StoreError> {
+        timed("revoke_access_token", sqlx::query("INSERT INTO revoked_access_tokens (jti, expires_at) VALUES ($1, $2) ON CONFLICT (jti) DO NOTHING")
+            .bind(jti)
+            .bind(expires_at)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool, StoreError> {
+        let revoked = timed("is_access_token_revoked", sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM revoked_access_tokens WHERE jti = $1)")
+            .bind(jti)
+            .fetch_one(&self.pool))
+            .await?;
+
+        Ok(revoked)
+    }
+
+    async fn purge_revoked_access_tokens(&self, expired_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        let result = timed("purge_revoked_access_tokens", sqlx::query("DELETE FROM revoked_access_tokens WHERE expires_at < $1")
+            .bind(expired_before)
+            .execute(&self.pool))
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+
+#[rocket::async_trait]
+impl EventStore for SqliteStore {
+    async fn record_event(&self, event: &NewAuthEvent) -> Result<(), StoreError> {
+        timed("record_event", sqlx::query("INSERT INTO auth_events (user_id, event_type, ip_address, user_agent, created_at) VALUES ($1, $2, $3, $4, $5)")
+            .bind(event.user_id)
+            .bind(event.event_type.as_str())
+            .bind(&event.ip_address)
+            .bind(&event.user_agent)
+            .bind(Utc::now())
+            .execute(&self.pool))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_events(&self, user_id: Option<i64>, limit: i64, offset: i64) -> Result<Vec<AuthEvent>, StoreError> {
+        let events = timed("list_events", sqlx::query_as("SELECT * FROM auth_events WHERE $1 IS NULL OR user_id = $1 ORDER BY created_at DESC, event_id DESC LIMIT $2 OFFSET $3")
+            .bind(user_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool))
+            .await?;
+
+        Ok(events)
+    }
+
+    async fn count_events(&self, user_id: Option<i64>) -> Result<i64, StoreError> {
+        let count = timed("count_events", sqlx::query_scalar("SELECT count(*) FROM auth_events WHERE $1 IS NULL OR user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool))
+            .await?;
+
+        Ok(count)
+    }
+}
\ No newline at end of file
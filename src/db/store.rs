@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::auth_events::{AuthEvent, NewAuthEvent};
+use crate::db::create_refresh_entry::RefreshToken;
+use crate::db::migrations::SchemaError;
+use crate::db::user::{User, UserArgs};
+
+/// Errors returned by the stores. Unique constraint violations are split out so every backend reports them the same way
+#[derive(Debug)]
+pub enum StoreError {
+    Conflict,
+    Backend(sqlx::error::Error)
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict => write!(f, "unique constraint violation"),
+            Self::Backend(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<sqlx::error::Error> for StoreError {
+    fn from(e: sqlx::error::Error) -> Self {
+        match &e {
+            sqlx::error::Error::Database(db_error) if db_error.is_unique_violation() => Self::Conflict,
+            _ => Self::Backend(e)
+        }
+    }
+}
+
+/// The connections of a database pool, exported as metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// The open connections, idle or in use
+    pub size: u32,
+    pub idle: u32,
+    pub max: u32
+}
+
+/// Storage for user accounts
+#[rocket::async_trait]
+pub trait UserStore: Send + Sync {
+    /// Creates a user; this function does not check the input. Returns the user_id of the new user, or StoreError::Conflict if the username is taken
+    async fn write_user(&self, user: &UserArgs) -> Result<i64, StoreError>;
+
+    /// Finds the users with a username; deleted users are not returned
+    async fn read_user(&self, username: &str) -> Result<Vec<User>, StoreError>;
+
+    /// Finds a user by their id; deleted users are not returned
+    async fn read_user_by_id(&self, user_id: i64) -> Result<Option<User>, StoreError>;
+
+    /// Finds a page of users ordered by user_id, optionally only those whose username contains the search text, ignoring case
+    async fn list_users(&self, search: Option<&str>, limit: i64, offset: i64) -> Result<Vec<User>, StoreError>;
+
+    /// Counts the users matching a search in the same way as list_users
+    async fn count_users(&self, search: Option<&str>) -> Result<i64, StoreError>;
+
+    /// Replaces the stored password hash of a user
+    async fn update_password(&self, user_id: i64, password: &str) -> Result<(), StoreError>;
+
+    /// Changes a user's username, or returns StoreError::Conflict if it is taken
+    async fn update_username(&self, user_id: i64, username: &str) -> Result<(), StoreError>;
+
+    /// Sets or clears a user's display name
+    async fn set_display_name(&self, user_id: i64, display_name: Option<&str>) -> Result<(), StoreError>;
+
+    /// Sets or clears a user's email address, which is unverified until the token with the given hash is presented
+    async fn set_email(&self, user_id: i64, email: Option<&str>, token_hash: Option<&str>, token_expires_at: Option<DateTime<Utc>>) -> Result<(), StoreError>;
+
+    /// Marks a user's email address as verified and removes the verification token
+    async fn mark_email_verified(&self, user_id: i64) -> Result<(), StoreError>;
+
+    /// Deletes a user along with their refresh sessions and roles. Returns false if there was no such user
+    async fn delete_user(&self, user_id: i64) -> Result<bool, StoreError>;
+
+    /// Marks a user as deleted and revokes their refresh sessions; the user is hidden from every lookup until purge_deleted_users removes them. Returns false if there was no such user
+    async fn soft_delete_user(&self, user_id: i64) -> Result<bool, StoreError>;
+
+    /// Removes the users deleted before a point in time along with their refresh sessions and roles. Returns the number of users removed
+    async fn purge_deleted_users(&self, deleted_before: DateTime<Utc>) -> Result<u64, StoreError>;
+
+    /// Disables or re-enables a user
+    async fn set_user_disabled(&self, user_id: i64, disabled: bool) -> Result<(), StoreError>;
+
+    /// Finds the roles assigned to a user in alphabetical order
+    async fn get_roles(&self, user_id: i64) -> Result<Vec<String>, StoreError>;
+
+    /// Assigns a role to a user, doing nothing if they already have it
+    async fn add_role(&self, user_id: i64, role: &str) -> Result<(), StoreError>;
+
+    /// Removes a role from a user
+    async fn remove_role(&self, user_id: i64, role: &str) -> Result<(), StoreError>;
+
+    /// The connections of the store's database pool, or None if it has no pool
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+
+    /// Checks that the database can be reached
+    async fn ping(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    /// Checks that the migrations applied to the database match the ones the server was built with
+    async fn check_schema(&self) -> Result<(), SchemaError> {
+        Ok(())
+    }
+
+    /// Closes the store's database pool, waiting for the connections in use to be returned. Called once the server has stopped
+    async fn close(&self) {}
+}
+
+/// Storage for refresh token sessions
+#[rocket::async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Stores a new refresh token
+    async fn create_refresh_entry(&self, user_id: i64, session_id: Uuid, token_hash: &str, expires_at: DateTime<Utc>) -> Result<(), StoreError>;
+
+    /// Finds a refresh token by its hash
+    async fn get_refresh_entry(&self, token_hash: &str) -> Result<Option<RefreshToken>, StoreError>;
+
+    /// Marks a refresh token as rotated and stores the token replacing it in the same session. Returns false if the token had already been rotated or revoked
+    async fn rotate_refresh_entry(&self, old_token: &RefreshToken, new_token_hash: &str, new_expires_at: DateTime<Utc>) -> Result<bool, StoreError>;
+
+    /// Revokes every refresh token in a session
+    async fn revoke_refresh_session(&self, session_id: Uuid) -> Result<(), StoreError>;
+
+    /// Finds the current token of each active session of a user, newest first
+    async fn list_refresh_sessions(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError>;
+
+    /// Finds every refresh token stored for a user, including rotated, revoked and expired ones, newest first
+    async fn list_refresh_tokens(&self, user_id: i64) -> Result<Vec<RefreshToken>, StoreError>;
+
+    /// Revokes every refresh session of a user
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<(), StoreError>;
+
+    /// Revokes every refresh session of every user
+    async fn revoke_all_sessions(&self) -> Result<(), StoreError>;
+
+    /// Adds a JWT's jti to the denylist until the JWT expires, doing nothing if it is already there
+    async fn revoke_access_token(&self, jti: Uuid, expires_at: DateTime<Utc>) -> Result<(), StoreError>;
+
+    /// Checks whether a JWT's jti is on the denylist
+    async fn is_access_token_revoked(&self, jti: Uuid) -> Result<bool, StoreError>;
+
+    /// Removes the denylist entries of JWTs that expired before a point in time. Returns the number of entries removed
+    async fn purge_revoked_access_tokens(&self, expired_before: DateTime<Utc>) -> Result<u64, StoreError>;
+}
+
+/// Storage for the audit log of authentication events. Events are removed along with their user when the user is removed for good
+#[rocket::async_trait]
+pub trait EventStore: Send + Sync {
+    /// Records an authentication event
+    async fn record_event(&self, event: &NewAuthEvent) -> Result<(), StoreError>;
+
+    /// Finds a page of events newest first, only those of one user when user_id is set
+    async fn list_events(&self, user_id: Option<i64>, limit: i64, offset: i64) -> Result<Vec<AuthEvent>, StoreError>;
+
+    /// Counts the events in the same way as list_events
+    async fn count_events(&self, user_id: Option<i64>) -> Result<i64, StoreError>;
+}
+
+pub type DynUserStore = Arc<dyn UserStore>;
+pub type DynSessionStore = Arc<dyn SessionStore>;
+pub type DynEventStore = Arc<dyn EventStore>;
+
+/// The stores managed by rocket for the routes
+#[derive(Clone)]
+pub struct Stores {
+    pub users: DynUserStore,
+    pub sessions: DynSessionStore,
+    pub events: DynEventStore
+}
+
+impl Stores {
+    pub fn postgres(pool: sqlx::PgPool) -> Self {
+        let store = Arc::new(crate::db::postgres_store::PgStore::new(pool));
+        Self { users: store.clone(), sessions: store.clone(), events: store }
+    }
+
+    pub fn memory() -> Self {
+        let store = Arc::new(crate::db::memory_store::MemoryStore::default());
+        Self { users: store.clone(), sessions: store.clone(), events: store }
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(pool: sqlx::SqlitePool) -> Self {
+        let store = Arc::new(crate::db::sqlite_store::SqliteStore::new(pool));
+        Self { users: store.clone(), sessions: store.clone(), events: store }
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+/// Changes a user's username; the unique index on username rejects names that are taken
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `username`: The new username
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn update_username(user_id: i64, username: &str, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET username = $2
+            WHERE user_id = $1
+            "#,
+            user_id,
+            username,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Sets or clears a user's display name
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `display_name`: The new display name, or None to remove it
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn set_display_name(user_id: i64, display_name: Option<&str>, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET display_name = $2
+            WHERE user_id = $1
+            "#,
+            user_id,
+            display_name,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Sets or clears a user's email address. The address starts out unverified, with the hash of the token that verifies it
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `email`: The new email address, or None to remove it
+/// - `token_hash`: The hash of the verification token sent to the address
+/// - `token_expires_at`: When the verification token stops being accepted
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn set_email(user_id: i64, email: Option<&str>, token_hash: Option<&str>, token_expires_at: Option<DateTime<Utc>>, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET email = $2, email_verified_at = NULL, email_token_hash = $3, email_token_expires_at = $4
+            WHERE user_id = $1
+            "#,
+            user_id,
+            email,
+            token_hash,
+            token_expires_at,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Marks a user's email address as verified and removes the verification token
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn mark_email_verified(user_id: i64, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET email_verified_at = now(), email_token_hash = NULL, email_token_expires_at = NULL
+            WHERE user_id = $1
+            "#,
+            user_id,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use sqlx::PgPool;
+
+/// Replaces the stored password hash of a user; this function does not check the input
+///
+/// # Arguments
+/// - `user_id`: The id of the user to update
+/// - `password`: The new hashed password
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn update_password(user_id: i64, password: &str, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            UPDATE users
+            SET password = $2
+            WHERE user_id = $1
+            "#,
+            user_id,
+            password,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -3,15 +3,32 @@ use rocket::serde::{Deserialize, Serialize};
 use rocket::request::{FromRequest, Outcome};
 use rocket::{Request, http::Status};
 
+use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 
 use crate::crypto::jwt::{JwtStatus, decode_jwt};
+use crate::crypto::session_cookies::access_token;
+use crate::db::store::{DynSessionStore, DynUserStore};
+use crate::metrics::collectors::METRICS;
 
-#[derive(FromRow, Deserialize, Serialize, Debug)]
+/// The role that grants access to the /admin routes
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(FromRow, Deserialize, Serialize, Debug, Clone)]
 pub struct User {
     pub user_id: i64,
     pub username: String,
-    pub password: String
+    pub password: String,
+    /// Set while the account is disabled by an administrator
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// The hash of the token sent to verify a new email address, and when it stops being accepted
+    pub email_token_hash: Option<String>,
+    pub email_token_expires_at: Option<DateTime<Utc>>,
+    /// Set when the account is deleted; the row is removed for good once the grace period has passed
+    pub deleted_at: Option<DateTime<Utc>>
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -21,6 +38,7 @@ pub struct UserArgs {
     pub password: String
 }
 
+/// A signed in user. The account and the revoked token denylist are checked on every request, so a deleted or disabled account or a revoked token is refused even while the JWT is still valid
 pub struct AuthUser {
     pub user_id: isize,
     pub username: String
@@ -31,24 +49,83 @@ impl<'r> FromRequest<'r> for AuthUser {
     type Error = ();
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // The JWT is taken from the Authorization header, or the access token cookie in browser session mode
+        let key = match access_token(request) {
+            Ok(key) => key,
+            Err(status) => return Outcome::Forward(status)
+        };
+
+        let user = match decode_jwt(&key) {
+            Ok(JwtStatus::Valid(user)) => user,
+            Ok(JwtStatus::Expired(_)) => return token_refused("expired", Status::Unauthorized),
+            Err(_) => return token_refused("invalid", Status::Unauthorized)
+        };
+        let (Some(users), Some(sessions)) = (request.rocket().state::<DynUserStore>(), request.rocket().state::<DynSessionStore>()) else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
 
-        match request.headers().get_one("Authorization") {
-            Some(header) => {
-                let Some(key) = header.strip_prefix("Bearer ") else {
-                    return Outcome::Forward(Status::BadRequest);
-                };
-
-                match decode_jwt(key) {
-                    Ok(JwtStatus::Valid(user)) => {
-                        Outcome::Success(AuthUser {
-                            user_id: user.user_id as isize,
-                            username: user.username
-                        })
-                    },
-                    _ => Outcome::Error((Status::Unauthorized, ()))
+        if let Some(jti) = user.jti {
+            match sessions.is_access_token_revoked(jti).await {
+                Ok(true) => return token_refused("revoked", Status::Unauthorized),
+                Ok(false) => {},
+                Err(e) => {
+                    rocket::error!("{}", e);
+                    return Outcome::Error((Status::InternalServerError, ()));
                 }
+            }
+        }
+
+        match users.read_user_by_id(user.user_id).await {
+            Ok(Some(account)) if account.disabled_at.is_some() => token_refused("account_disabled", Status::Forbidden),
+            Ok(Some(_)) => {
+                Outcome::Success(AuthUser {
+                    user_id: user.user_id as isize,
+                    username: user.username
+                })
+            },
+            Ok(None) => token_refused("account_not_found", Status::Unauthorized),
+            Err(e) => {
+                rocket::error!("{}", e);
+                Outcome::Error((Status::InternalServerError, ()))
+            }
+        }
+    }
+}
+
+/// Counts a token refused by AuthUser for the auth_token_validation_failures_total metric
+fn token_refused<T>(reason: &str, status: Status) -> Outcome<T, ()> {
+    METRICS.token_validation_failures.with_label_values(&[reason]).inc();
+    Outcome::Error((status, ()))
+}
+
+/// A signed in user with the admin role. Roles are read from the user store on every request, so removing the role takes effect immediately
+pub struct AdminUser {
+    pub user_id: i64,
+    pub username: String
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = rocket::outcome::try_outcome!(request.guard::<AuthUser>().await);
+        let Some(users) = request.rocket().state::<DynUserStore>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        match users.get_roles(user.user_id as i64).await {
+            Ok(roles) if roles.iter().any(|role| role == ADMIN_ROLE) => {
+                Outcome::Success(AdminUser {
+                    user_id: user.user_id as i64,
+                    username: user.username
+                })
             },
-            None => Outcome::Forward(Status::Unauthorized)
+            Ok(_) => Outcome::Error((Status::Forbidden, ())),
+            Err(e) => {
+                rocket::error!("{}", e);
+                Outcome::Error((Status::InternalServerError, ()))
+            }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,73 @@
+use sqlx::PgPool;
+
+/// Finds the roles assigned to a user
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with the role names in alphabetical order or an sql::error::Error enum if the operation is not successful
+pub async fn get_roles(user_id: i64, pool: &PgPool) -> Result<Vec<String>,  sqlx::error::Error> {
+    let roles = sqlx::query_scalar!(
+            r#"
+            SELECT role FROM user_roles
+            WHERE user_id = $1
+            ORDER BY role
+            "#,
+            user_id
+            )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(roles)
+}
+
+/// Assigns a role to a user, doing nothing if they already have it
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `role`: The name of the role
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn add_role(user_id: i64, role: &str, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            user_id,
+            role,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Removes a role from a user
+///
+/// # Arguments
+/// - `user_id`: The id of the user
+/// - `role`: The name of the role
+/// - `pool`: A reference to a PgPool which is required to connect to SQL databases
+///
+/// # Returns
+/// A Result enum with no data or an sql::error::Error enum if the operation is not successful
+pub async fn remove_role(user_id: i64, role: &str, pool: &PgPool) -> Result<(),  sqlx::error::Error> {
+    sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            WHERE user_id = $1 AND role = $2
+            "#,
+            user_id,
+            role,
+            )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
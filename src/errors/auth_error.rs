@@ -0,0 +1,156 @@
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+
+use crate::db::store::StoreError;
+use crate::logs::request_id::RequestId;
+use crate::mail::mailer::MailError;
+use crate::routes::responses::{ApiError, INTERNAL_ERROR};
+
+/// Errors returned by the routes. Library errors convert into this with `?`, and it responds with the JSON error envelope, mapping each variant to a status and a machine-readable code
+#[derive(Debug)]
+pub enum AuthError {
+    Database(StoreError),
+    Hashing(argon2::password_hash::Error),
+    Token(jsonwebtoken::errors::Error),
+    Random(rustls::crypto::GetRandomFailed),
+    Mail(MailError),
+    Validation(String),
+    RateLimited,
+    UserExists,
+    AccountNotFound,
+    AccountConflict,
+    AccountDisabled,
+    IncorrectPassword,
+    MissingRefreshToken,
+    InvalidRefreshToken,
+    UserNotFound,
+    SessionNotFound,
+    InvalidEmailToken
+}
+
+impl AuthError {
+    pub fn status(&self) -> Status {
+        match self {
+            Self::Database(_) | Self::Hashing(_) | Self::Random(_) | Self::Mail(_) | Self::AccountConflict => Status::InternalServerError,
+            Self::Token(_) | Self::IncorrectPassword | Self::MissingRefreshToken | Self::InvalidRefreshToken => Status::Unauthorized,
+            Self::Validation(_) | Self::InvalidEmailToken => Status::UnprocessableEntity,
+            Self::RateLimited => Status::TooManyRequests,
+            Self::UserExists => Status::Conflict,
+            Self::AccountNotFound | Self::UserNotFound | Self::SessionNotFound => Status::NotFound,
+            Self::AccountDisabled => Status::Forbidden
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database_error",
+            Self::Hashing(_) => "hashing_error",
+            Self::Token(_) => "invalid_token",
+            Self::Random(_) => "internal_error",
+            Self::Mail(_) => "mail_error",
+            Self::Validation(_) => "validation_error",
+            Self::RateLimited => "rate_limited",
+            Self::UserExists => "user_exists",
+            Self::AccountNotFound => "account_not_found",
+            Self::AccountConflict => "account_conflict",
+            Self::AccountDisabled => "account_disabled",
+            Self::IncorrectPassword => "incorrect_password",
+            Self::MissingRefreshToken => "missing_refresh_token",
+            Self::InvalidRefreshToken => "invalid_refresh_token",
+            Self::UserNotFound => "user_not_found",
+            Self::SessionNotFound => "session_not_found",
+            Self::InvalidEmailToken => "invalid_email_token"
+        }
+    }
+
+    /// The message shown to the client; details of server errors are only written to the log
+    pub fn message(&self) -> String {
+        match self {
+            Self::Database(_) | Self::Hashing(_) | Self::Random(_) => INTERNAL_ERROR.to_string(),
+            Self::Mail(_) => "The email could not be sent, please try again later".to_string(),
+            Self::Token(_) => "The token is invalid".to_string(),
+            Self::Validation(message) => message.clone(),
+            Self::RateLimited => "Too many requests, please try again later".to_string(),
+            Self::UserExists => "User already exists".to_string(),
+            Self::AccountNotFound => "No account found by that username".to_string(),
+            Self::AccountConflict => "An error with this account has occured, please contact support".to_string(),
+            Self::AccountDisabled => "This account has been disabled".to_string(),
+            Self::IncorrectPassword => "Password is incorrect".to_string(),
+            Self::MissingRefreshToken => "No valid refresh token".to_string(),
+            Self::InvalidRefreshToken => "Refresh token is invalid".to_string(),
+            Self::UserNotFound => "No user found with that id".to_string(),
+            Self::SessionNotFound => "No active session found with that id".to_string(),
+            Self::InvalidEmailToken => "The verification code is invalid or has expired".to_string()
+        }
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "database error: {}", e),
+            Self::Hashing(e) => write!(f, "hashing error: {}", e),
+            Self::Token(e) => write!(f, "token error: {}", e),
+            Self::Random(e) => write!(f, "random generation failed: {:?}", e),
+            Self::Mail(e) => write!(f, "{}", e),
+            _ => write!(f, "{}", self.message())
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl From<StoreError> for AuthError {
+    fn from(e: StoreError) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<sqlx::error::Error> for AuthError {
+    fn from(e: sqlx::error::Error) -> Self {
+        Self::Database(StoreError::from(e))
+    }
+}
+
+impl From<MailError> for AuthError {
+    fn from(e: MailError) -> Self {
+        Self::Mail(e)
+    }
+}
+
+impl From<argon2::password_hash::Error> for AuthError {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        Self::Hashing(e)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AuthError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        Self::Token(e)
+    }
+}
+
+impl From<rustls::crypto::GetRandomFailed> for AuthError {
+    fn from(e: rustls::crypto::GetRandomFailed) -> Self {
+        Self::Random(e)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AuthError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        // The request id is returned to the client as the correlation id, and tags this and every other log line of the request, so a report can be matched to the logs
+        let correlation_id = RequestId::of(request);
+        let status = self.status();
+
+        if status.class().is_server_error() {
+            rocket::error!("{} {}: {}", request.method(), request.uri(), self);
+        } else {
+            rocket::info!("{} {}: {}", request.method(), request.uri(), self);
+        }
+
+        ApiError::new(status, self.code(), &self.message())
+            .with_correlation_id(correlation_id)
+            .respond_to(request)
+    }
+}
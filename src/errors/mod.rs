@@ -0,0 +1 @@
+pub mod auth_error;
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod purge_deleted_users;
+pub mod purge_revoked_tokens;
\ No newline at end of file
@@ -0,0 +1,70 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+use rocket::fairing::AdHoc;
+
+use crate::config::env_vars::env_or;
+use crate::db::store::DynUserStore;
+
+/// How long deleted accounts are kept before they are removed for good, and how often the server checks for them
+pub struct PurgeConfig {
+    pub grace_period: Duration,
+    pub interval: StdDuration
+}
+
+impl PurgeConfig {
+    /// Reads DELETED_USER_GRACE_PERIOD (default 30 days) and DELETED_USER_PURGE_INTERVAL (default an hour, 0 turns the job off), both in seconds. The process exits if the grace period is out of range
+    pub fn from_env() -> Self {
+        let grace_period = Duration::try_seconds(env_or("DELETED_USER_GRACE_PERIOD", 30 * 24 * 60 * 60))
+            .filter(|grace_period| Utc::now().checked_sub_signed(*grace_period).is_some());
+        let Some(grace_period) = grace_period else {
+            eprintln!("DELETED_USER_GRACE_PERIOD is out of range in .env");
+            std::process::exit(1)
+        };
+
+        Self {
+            grace_period,
+            interval: StdDuration::from_secs(env_or("DELETED_USER_PURGE_INTERVAL", 60 * 60))
+        }
+    }
+}
+
+/// Removes the accounts whose grace period has passed
+///
+/// # Arguments
+/// - `users`: The user store
+/// - `grace_period`: How long deleted accounts are kept
+///
+/// # Returns
+/// The number of accounts removed, or the store error
+pub async fn purge_deleted_users(users: &DynUserStore, grace_period: Duration) -> Result<u64, crate::db::store::StoreError> {
+    users.purge_deleted_users(Utc::now() - grace_period).await
+}
+
+/// A fairing that starts a background task once the server is running, removing deleted accounts after their grace period
+///
+/// # Returns
+/// The fairing to attach to rocket
+pub fn purge_fairing() -> AdHoc {
+    AdHoc::on_liftoff("Purge deleted users", |rocket| Box::pin(async move {
+        let config = PurgeConfig::from_env();
+        let Some(users) = rocket.state::<DynUserStore>().cloned() else {
+            return;
+        };
+        if config.interval.is_zero() {
+            return;
+        }
+
+        rocket::tokio::spawn(async move {
+            let mut interval = rocket::tokio::time::interval(config.interval);
+            loop {
+                interval.tick().await;
+                match purge_deleted_users(&users, config.grace_period).await {
+                    Ok(0) => {},
+                    Ok(count) => rocket::info!("Removed {} deleted users after their grace period", count),
+                    Err(e) => rocket::error!("Failed to remove deleted users: {}", e)
+                }
+            }
+        });
+    }))
+}
@@ -0,0 +1,35 @@
+use std::time::Duration as StdDuration;
+
+use chrono::Utc;
+use rocket::fairing::AdHoc;
+
+use crate::config::env_vars::env_or;
+use crate::db::store::DynSessionStore;
+
+/// A fairing that starts a background task once the server is running, removing the denylist entries of revoked JWTs that have since expired. It runs every REVOKED_TOKEN_PURGE_INTERVAL seconds (default an hour, 0 turns it off)
+///
+/// # Returns
+/// The fairing to attach to rocket
+pub fn purge_revoked_tokens_fairing() -> AdHoc {
+    AdHoc::on_liftoff("Purge revoked tokens", |rocket| Box::pin(async move {
+        let interval = StdDuration::from_secs(env_or("REVOKED_TOKEN_PURGE_INTERVAL", 60 * 60));
+        let Some(sessions) = rocket.state::<DynSessionStore>().cloned() else {
+            return;
+        };
+        if interval.is_zero() {
+            return;
+        }
+
+        rocket::tokio::spawn(async move {
+            let mut interval = rocket::tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match sessions.purge_revoked_access_tokens(Utc::now()).await {
+                    Ok(0) => {},
+                    Ok(count) => rocket::info!("Removed {} expired entries from the revoked token denylist", count),
+                    Err(e) => rocket::error!("Failed to remove expired revoked tokens: {}", e)
+                }
+            }
+        });
+    }))
+}
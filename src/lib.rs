@@ -1,4 +1,76 @@
 pub mod routes;
 pub mod db;
 pub mod crypto;
-pub mod logs;
\ No newline at end of file
+pub mod logs;
+pub mod config;
+pub mod cli;
+pub mod errors;
+pub mod mail;
+pub mod jobs;
+pub mod metrics;
+pub mod telemetry;
+
+use std::sync::Arc;
+
+use routes::accounts::create_account::{create, test_route};
+use routes::accounts::signin::signin;
+use routes::accounts::refresh_token::refresh;
+use routes::accounts::logout::logout;
+use routes::accounts::profile::{get_profile, update_profile, verify_email, change_username, delete_account, export_account};
+use routes::admin::users::{list_users, get_user, disable_user, enable_user, reset_password, delete_user};
+use routes::accounts::security_events::list_security_events;
+use routes::admin::sessions::{list_sessions, revoke_sessions, revoke_session};
+use routes::admin::security_events::{list_all_events, list_user_events};
+use routes::catchers::default_catcher;
+use routes::metrics::export_metrics;
+use routes::health::{healthz, readyz};
+use routes::jwks::publish_jwks;
+use routes::tokens::introspect::introspect;
+use routes::tokens::revoke::revoke;
+
+use db::store::Stores;
+use jobs::purge_deleted_users::purge_fairing;
+use jobs::purge_revoked_tokens::purge_revoked_tokens_fairing;
+use logs::request_id::{with_request_id, RequestIdFairing};
+use metrics::fairing::MetricsFairing;
+use telemetry::request_span::with_tracing;
+use mail::mailer::{DynMailer, LogMailer};
+
+/// Builds the rocket server with all routes mounted; used by main and by the integration tests. Emails are written to the log
+///
+/// # Arguments
+/// - `stores`: The user and session stores managed by rocket for the routes
+///
+/// # Returns
+/// A rocket instance ready to be launched
+pub fn build_rocket(stores: Stores) -> rocket::Rocket<rocket::Build> {
+    build_rocket_with_mailer(stores, Arc::new(LogMailer))
+}
+
+/// Builds the rocket server with all routes mounted, sending emails with the given mailer
+///
+/// # Arguments
+/// - `stores`: The user and session stores managed by rocket for the routes
+/// - `mailer`: The mailer used to send email verification codes
+///
+/// # Returns
+/// A rocket instance ready to be launched
+pub fn build_rocket_with_mailer(stores: Stores, mailer: DynMailer) -> rocket::Rocket<rocket::Build> {
+    rocket::build()
+        .manage(stores.users)
+        .manage(stores.sessions)
+        .manage(stores.events)
+        .manage(mailer)
+        .mount("/", with_tracing(with_request_id(rocket::routes![create, signin, test_route, refresh, logout, export_metrics, healthz, readyz, publish_jwks, introspect, revoke])))
+        .mount("/", with_tracing(with_request_id(rocket::routes![get_profile, update_profile, verify_email, change_username, delete_account, export_account, list_security_events])))
+        .mount("/admin", with_tracing(with_request_id(rocket::routes![
+            list_users, get_user, disable_user, enable_user, reset_password, delete_user,
+            list_sessions, revoke_sessions, revoke_session,
+            list_all_events, list_user_events
+        ])))
+        .register("/", rocket::catchers![default_catcher])
+        .attach(RequestIdFairing)
+        .attach(MetricsFairing)
+        .attach(purge_fairing())
+        .attach(purge_revoked_tokens_fairing())
+}
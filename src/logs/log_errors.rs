@@ -1,36 +1,128 @@
-use std::fs::OpenOptions;
-use chrono::Local;
+use std::io::Write;
+use std::str::FromStr;
+use chrono::{DateTime, Local, SecondsFormat};
 use fern::Dispatch;
-use log::LevelFilter;
+use log::{Level, LevelFilter};
+use rocket::serde::json::serde_json;
 
-// Function to set up logging
-pub fn setup_logging() -> Result<(), fern::InitError> {
-    let log_file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .append(true)
-        .open("application_errors.log")?; // Specify your log file name
+use crate::config::env_vars::env_or;
+use crate::logs::redact::redact;
+use crate::logs::request_id::current_request_id;
+use crate::logs::rotation::{RotatingFile, RotationConfig};
 
-    Dispatch::new()
+/// How log lines are written, set with LOG_FORMAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[time][level][target][request id] message`, for reading in a terminal
+    Text,
+    /// One JSON object per line, for log collectors
+    Json
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format {}", value))
+        }
+    }
+}
+
+/// The levels set with LOG_LEVEL: a default level followed by levels for modules, such as `info,rocket=warn,server::db=debug`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLevels {
+    pub default: LevelFilter,
+    pub modules: Vec<(String, LevelFilter)>
+}
+
+impl FromStr for LogLevels {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut levels = LogLevels { default: LevelFilter::Info, modules: Vec::new() };
+
+        for directive in value.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let level = level.trim().parse().map_err(|_| format!("unknown log level {}", level))?;
+                    levels.modules.push((module.trim().to_string(), level));
+                },
+                None => levels.default = directive.parse().map_err(|_| format!("unknown log level {}", directive))?
+            }
+        }
+
+        Ok(levels)
+    }
+}
+
+/// Formats a log line, removing secrets from the message
+///
+/// # Arguments
+/// - `format`: Text or JSON
+/// - `time`: When the line was logged
+/// - `level`: The level of the line
+/// - `target`: The module that logged it
+/// - `request_id`: The id of the request being handled, if any
+/// - `message`: The message
+///
+/// # Returns
+/// The line without a trailing newline
+pub fn format_line(format: LogFormat, time: DateTime<Local>, level: Level, target: &str, request_id: Option<&str>, message: &str) -> String {
+    let message = redact(message);
+
+    match format {
+        LogFormat::Text => {
+            let request_id = request_id.map(|request_id| format!("[{}]", request_id)).unwrap_or_default();
+            format!("[{}][{}][{}]{} {}", time.format("%Y-%m-%d %H:%M:%S"), level, target, request_id, message)
+        },
+        LogFormat::Json => {
+            let mut line = serde_json::json!({
+                "timestamp": time.to_rfc3339_opts(SecondsFormat::Millis, false),
+                "level": level.as_str(),
+                "target": target,
+                "message": message
+            });
+            if let Some(request_id) = request_id {
+                line["request_id"] = request_id.into();
+            }
+            line.to_string()
+        }
+    }
+}
+
+// Function to set up logging, returning the log file so it can be reopened on SIGHUP
+pub fn setup_logging() -> Result<RotatingFile, fern::InitError> {
+    // The file is set with LOG_FILE and rotated as configured in RotationConfig
+    let log_file = RotatingFile::open(RotationConfig::from_env())?;
+
+    let format = env_or("LOG_FORMAT", LogFormat::Text);
+    let levels = env_or("LOG_LEVEL", LogLevels { default: LevelFilter::Info, modules: Vec::new() });
+
+    let mut dispatch = Dispatch::new()
         // Set the default minimum log level for everything
-        .level(LevelFilter::Info)
-        // Filter out excessive logs from specific modules if needed
-        // .level_for("rocket", LevelFilter::Warn)
+        .level(levels.default);
+    // Then the levels of single modules, such as rocket=warn
+    for (module, level) in levels.modules {
+        dispatch = dispatch.level_for(module, level);
+    }
+
+    dispatch
         // Format the log messages
-        .format(|out, message, record| {
+        .format(move |out, message, record| {
+            let request_id = current_request_id();
             out.finish(format_args!(
-                "[{}][{}][{}] {}",
-                Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level(),
-                record.target(),
-                message
+                "{}",
+                format_line(format, Local::now(), record.level(), record.target(), request_id.as_deref(), &message.to_string())
             ))
         })
         // Do not send to standard output (optional, you can also chain both)
         .chain(std::io::stdout())
         // Chain the log file appender
-        .chain(log_file)
+        .chain(Box::new(log_file.clone()) as Box<dyn Write + Send>)
         .apply()?; // Apply the configuration
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(log_file)
+}
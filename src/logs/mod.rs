@@ -1 +1,4 @@
-pub mod log_errors;
\ No newline at end of file
+pub mod log_errors;
+pub mod redact;
+pub mod request_id;
+pub mod rotation;
\ No newline at end of file
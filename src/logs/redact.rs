@@ -0,0 +1,44 @@
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A key that names a secret, such as password, refresh_token or Set-Cookie, followed by : or = and its value, quoted or not
+static SECRET_FIELD: LazyLock<Regex> = LazyLock::new(|| Regex::new(
+    r#"(?i)("?[\w-]*(?:password|passwd|secret|token|cookie|authorization|api_key)[\w-]*"?\s*[:=]\s*)(?:"((?:[^"\\]|\\.)*)"|([^\s,;&)}\]]+))"#
+).unwrap());
+
+/// The credentials of an Authorization header
+static BEARER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(Bearer|Basic)\s+[A-Za-z0-9\-._~+/]+=*").unwrap());
+
+/// Anything shaped like a JWT, wherever it appears
+static JWT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\beyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap());
+
+/// Removes passwords, tokens and cookies from a log message. Values of fields named like a secret are replaced, as are bearer credentials and JWTs found anywhere in the message
+///
+/// # Arguments
+/// - `message`: The formatted log message
+///
+/// # Returns
+/// The message with secrets replaced by [REDACTED], borrowed if there were none
+pub fn redact(message: &str) -> Cow<'_, str> {
+    let message = replace(message, &SECRET_FIELD, |caps| match caps.get(2) {
+        Some(_) => format!("{}\"{}\"", &caps[1], REDACTED),
+        None => format!("{}{}", &caps[1], REDACTED)
+    });
+    let message = replace_owned(message, &BEARER, |caps| format!("{} {}", &caps[1], REDACTED));
+    replace_owned(message, &JWT, |_| REDACTED.to_string())
+}
+
+fn replace<'a>(message: &'a str, pattern: &Regex, replacement: impl Fn(&Captures) -> String) -> Cow<'a, str> {
+    pattern.replace_all(message, |caps: &Captures| replacement(caps))
+}
+
+fn replace_owned<'a>(message: Cow<'a, str>, pattern: &Regex, replacement: impl Fn(&Captures) -> String) -> Cow<'a, str> {
+    match message {
+        Cow::Borrowed(message) => replace(message, pattern, replacement),
+        Cow::Owned(message) => Cow::Owned(replace(&message, pattern, replacement).into_owned())
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::Instant;
+
+use rocket::data::Data;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::route::{self, Handler, Route};
+use rocket::{Request, Response};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+rocket::tokio::task_local! {
+    /// The id of the request being handled, read by the log formatter
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// The id of a request, taken from the X-Request-Id header sent by a proxy or client, or generated if there is none
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// The id of a request, generating one if the fairing has not already set it
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r str {
+        &request.local_cache(|| RequestId(Uuid::new_v4().to_string())).0
+    }
+}
+
+/// The id of the request whose route is running on this task, if any
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+/// Gives every request an id, echoed in the X-Request-Id response header, and logs a line for each response
+pub struct RequestIdFairing;
+
+/// When the request started, for the response log line
+struct RequestStart(Instant);
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info { name: "Request id", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        // Ids from the client are kept only if they are short and plain, so they cannot be used to forge log lines
+        let request_id = request.headers().get_one(REQUEST_ID_HEADER)
+            .filter(|request_id| is_valid(request_id))
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        request.local_cache(|| RequestId(request_id));
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let request_id = RequestId::of(request).to_string();
+        let elapsed = request.local_cache(|| RequestStart(Instant::now())).0.elapsed();
+
+        response.set_header(Header::new(REQUEST_ID_HEADER, request_id.clone()));
+        CURRENT_REQUEST_ID.sync_scope(request_id, || {
+            log::info!(target: "server::access", "{} {} {} {}ms", request.method(), request.uri().path(), response.status().code, elapsed.as_millis());
+        });
+    }
+}
+
+/// Runs each route's handler with the request id set, so lines logged by the route and its guards are tagged with it
+///
+/// # Arguments
+/// - `routes`: The routes to mount
+///
+/// # Returns
+/// The same routes with their handlers wrapped
+pub fn with_request_id(routes: Vec<Route>) -> Vec<Route> {
+    routes.into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(RequestIdHandler(route.handler));
+            route
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct RequestIdHandler(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for RequestIdHandler {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let request_id = RequestId::of(request).to_string();
+        CURRENT_REQUEST_ID.scope(request_id, self.0.handle(request, data)).await
+    }
+}
+
+fn is_valid(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
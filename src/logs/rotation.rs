@@ -0,0 +1,288 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Datelike, Local};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::config::env_vars::env_or;
+
+/// How often the log file is rotated regardless of its size, set with LOG_ROTATE_INTERVAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationInterval {
+    Never,
+    Hourly,
+    Daily
+}
+
+impl FromStr for RotationInterval {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            _ => Err(format!("unknown rotation interval {}", value))
+        }
+    }
+}
+
+impl RotationInterval {
+    /// The period a time falls in; the file is rotated when the period changes
+    fn period(&self, time: DateTime<Local>) -> Option<i64> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(time.timestamp().div_euclid(3600)),
+            Self::Daily => Some(time.date_naive().num_days_from_ce() as i64)
+        }
+    }
+}
+
+/// Where the log file is written and when it is rotated
+#[derive(Debug, Clone)]
+pub struct RotationConfig {
+    pub path: PathBuf,
+    /// The size in bytes after which the file is rotated, or 0 to only rotate by time
+    pub max_size: u64,
+    pub interval: RotationInterval,
+    /// The number of rotated files kept; older ones are deleted
+    pub retention: usize,
+    /// Whether rotated files are gzipped
+    pub compress: bool
+}
+
+impl RotationConfig {
+    /// Reads LOG_FILE (default application_errors.log), LOG_MAX_SIZE (default 10 MiB), LOG_ROTATE_INTERVAL (never, hourly or daily, default daily), LOG_RETENTION (default 7) and LOG_COMPRESS (default true)
+    pub fn from_env() -> Self {
+        Self {
+            path: PathBuf::from(env_or("LOG_FILE", "application_errors.log".to_string())),
+            max_size: env_or("LOG_MAX_SIZE", 10 * 1024 * 1024),
+            interval: env_or("LOG_ROTATE_INTERVAL", RotationInterval::Daily),
+            retention: env_or("LOG_RETENTION", 7),
+            compress: env_or("LOG_COMPRESS", true)
+        }
+    }
+}
+
+/// A log file that rotates itself by size and time. Rotated files are renamed with the time they were rotated, such as application_errors.log.20250102-030405.123, then gzipped and pruned on a background thread, one rotation at a time. Clones write to the same file
+#[derive(Clone)]
+pub struct RotatingFile {
+    inner: Arc<Mutex<Inner>>
+}
+
+struct Inner {
+    config: RotationConfig,
+    file: File,
+    size: u64,
+    period: Option<i64>,
+    /// Rotation only happens between lines, so a line is never split across files
+    at_line_start: bool
+}
+
+impl RotatingFile {
+    /// Opens the log file for appending, creating it if needed
+    ///
+    /// # Arguments
+    /// - `config`: Where the file is and when it is rotated
+    ///
+    /// # Returns
+    /// The RotatingFile, or the io::Error from opening it
+    pub fn open(config: RotationConfig) -> io::Result<Self> {
+        let (file, size, period) = open_file(&config)?;
+        Ok(Self { inner: Arc::new(Mutex::new(Inner { config, file, size, period, at_line_start: true })) })
+    }
+
+    /// Opens the file at the configured path again, for when another tool such as logrotate has moved it away
+    ///
+    /// # Returns
+    /// Nothing, or the io::Error from opening the file
+    pub fn reopen(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (file, size, period) = open_file(&inner.config)?;
+        inner.file = file;
+        inner.size = size;
+        inner.period = period;
+        Ok(())
+    }
+
+    /// Rotates the file now, waiting for the rotated file to be compressed and old files to be pruned
+    ///
+    /// # Returns
+    /// Nothing, or the io::Error from renaming or opening the file
+    pub fn rotate(&self) -> io::Result<()> {
+        let (config, rotated) = {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            let rotated = inner.rotate()?;
+            (inner.config.clone(), rotated)
+        };
+        finish_rotation(&config, &rotated);
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        if inner.at_line_start && inner.needs_rotation(buf.len() as u64) {
+            let rotated = inner.rotate()?;
+            let config = inner.config.clone();
+            // Compressing a large file would hold up logging, so it is done off the logging thread
+            std::thread::spawn(move || finish_rotation(&config, &rotated));
+        }
+
+        let written = inner.file.write(buf)?;
+        inner.size += written as u64;
+        if written > 0 {
+            inner.at_line_start = buf[written - 1] == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).file.flush()
+    }
+}
+
+impl Inner {
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        let too_large = self.config.max_size > 0 && self.size > 0 && self.size + incoming > self.config.max_size;
+        let new_period = self.config.interval.period(Local::now()) != self.period;
+
+        too_large || new_period
+    }
+
+    /// Renames the current file and opens a new one in its place, returning the path it was renamed to
+    fn rotate(&mut self) -> io::Result<PathBuf> {
+        self.file.flush()?;
+
+        let stamp = Local::now().format("%Y%m%d-%H%M%S%.3f");
+        let mut rotated = sibling(&self.config.path, &format!(".{}", stamp));
+        let mut attempt = 1;
+        while rotated.exists() || sibling(&rotated, ".gz").exists() {
+            rotated = sibling(&self.config.path, &format!(".{}-{}", stamp, attempt));
+            attempt += 1;
+        }
+        fs::rename(&self.config.path, &rotated)?;
+
+        let (file, size, _) = open_file(&self.config)?;
+        self.file = file;
+        self.size = size;
+        self.period = self.config.interval.period(Local::now());
+        Ok(rotated)
+    }
+}
+
+/// Opens the log file, returning it with its size and the period it was last written in, so a file left from an earlier day is rotated on the first write
+fn open_file(config: &RotationConfig) -> io::Result<(File, u64, Option<i64>)> {
+    let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+    let metadata = file.metadata()?;
+
+    let last_written = match metadata.len() {
+        0 => Local::now(),
+        _ => metadata.modified().map(DateTime::<Local>::from).unwrap_or_else(|_| Local::now())
+    };
+
+    Ok((file, metadata.len(), config.interval.period(last_written)))
+}
+
+/// Compresses a rotated file if enabled and deletes the rotated files beyond the retention count. Errors are printed rather than logged, as logging them could rotate again
+fn finish_rotation(config: &RotationConfig, rotated: &Path) {
+    // Rotations finish one at a time, so pruning never counts a file that is half compressed or deletes one still being read
+    static FINISHING: Mutex<()> = Mutex::new(());
+    let _finishing = FINISHING.lock().unwrap_or_else(|e| e.into_inner());
+    // A later rotation that finished first may already have pruned this file
+    if !rotated.exists() {
+        return;
+    }
+
+    if config.compress && let Err(e) = compress(rotated) {
+        eprintln!("Failed to compress {}: {}", rotated.display(), e);
+    }
+
+    if let Err(e) = prune(config) {
+        eprintln!("Failed to remove old log files: {}", e);
+    }
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let compressed = sibling(path, ".gz");
+    let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+
+    fs::remove_file(path)
+}
+
+/// Deletes the oldest rotated files so only the configured number are kept. The timestamps in their names sort by age once the .gz extension is ignored
+fn prune(config: &RotationConfig) -> io::Result<()> {
+    let mut rotated = rotated_files(&config.path)?;
+    rotated.sort_by_key(|path| path.to_string_lossy().trim_end_matches(".gz").to_string());
+
+    let excess = rotated.len().saturating_sub(config.retention);
+    for path in rotated.into_iter().take(excess) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Finds the rotated files of a log file, compressed or not
+///
+/// # Arguments
+/// - `path`: The path of the log file
+///
+/// # Returns
+/// The paths of the rotated files in no particular order, or an io::Error from reading the directory
+pub fn rotated_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new(".")
+    };
+    let prefix = format!("{}.", path.file_name().unwrap_or_default().to_string_lossy());
+
+    let mut rotated = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix) && name[prefix.len()..].starts_with(|c: char| c.is_ascii_digit()) {
+            rotated.push(entry.path());
+        }
+    }
+    Ok(rotated)
+}
+
+/// Reopens the log file whenever the process receives SIGHUP, so it works alongside logrotate's default create mode
+///
+/// # Arguments
+/// - `file`: The log file to reopen
+#[cfg(unix)]
+pub fn reopen_on_sighup(file: RotatingFile) {
+    use rocket::tokio::signal::unix::{signal, SignalKind};
+
+    rocket::tokio::spawn(async move {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                rocket::error!("Failed to listen for SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        while hangups.recv().await.is_some() {
+            match file.reopen() {
+                Ok(()) => rocket::info!("Reopened the log file after SIGHUP"),
+                Err(e) => rocket::error!("Failed to reopen the log file: {}", e)
+            }
+        }
+    });
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
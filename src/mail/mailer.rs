@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+/// An email that could not be sent
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to send email: {}", self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// Sends emails to users, such as email address verification codes. A mail provider is added by implementing this and passing it to build_rocket_with_mailer
+#[rocket::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), MailError>;
+}
+
+pub type DynMailer = Arc<dyn Mailer>;
+
+/// The default mailer, which writes emails to the log instead of sending them. Only suitable for development
+pub struct LogMailer;
+
+#[rocket::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), MailError> {
+        rocket::info!("Email to {}: {}\n{}", to, subject, body);
+        Ok(())
+    }
+}
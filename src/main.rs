@@ -1,39 +1,72 @@
-use server::routes::accounts::create_account::{create, test_route};
-use server::routes::accounts::signin::signin;
-use server::routes::accounts::refresh_token::refresh;
-
 use rustls::crypto::CryptoProvider;
 
-use sqlx::postgres::PgPoolOptions;
-
 use dotenv::dotenv;
 use std::env;
 
+use server::build_rocket;
+use server::config::env_vars::env_or;
+use server::config::shutdown::with_shutdown_config;
+use server::db::connect::connect_stores;
 use server::logs::log_errors::setup_logging;
+use server::telemetry::otel::setup_tracing;
 
 #[rocket::main]
-async fn main() -> Result<(), rocket::Error> {
-    // crypto setup
-    CryptoProvider::install_default(
-        rustls::crypto::aws_lc_rs::default_provider()
-        ).expect("Failed to install default crypto provider");
+async fn main() -> Result<(), Box<rocket::Error>> {
+    // crypto setup, a provider that is already installed is kept
+    if CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).is_err() {
+        eprintln!("A crypto provider is already installed, using it");
+    }
+
+    // 'server migrate' applies the migrations and exits
+    let migrate_only = match env::args().nth(1).as_deref() {
+        None => false,
+        Some("migrate") => true,
+        Some(command) => {
+            eprintln!("Unknown command {}, the only command is migrate", command);
+            std::process::exit(1)
+        }
+    };
 
     //sql stuff
     dotenv().ok();
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env");
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
-        .await.expect("Database pool error");
+    let run_migrations = migrate_only || env_or("RUN_MIGRATIONS", false);
+    let stores = connect_stores(run_migrations).await;
+
+    if migrate_only {
+        println!("Database schema is up to date");
+        return Ok(());
+    }
 
     // logging setup
-    let _ = setup_logging().expect("Failed to initilize logging");
+    let log_file = setup_logging().expect("Failed to initilize logging");
+    #[cfg(unix)]
+    server::logs::rotation::reopen_on_sighup(log_file);
+    #[cfg(not(unix))]
+    drop(log_file);
+
+    // tracing setup, spans are only exported when an OTLP endpoint is configured
+    let tracer_provider = setup_tracing().unwrap_or_else(|e| {
+        rocket::error!("Tracing is disabled: {}", e);
+        None
+    });
 
-    let _rocket = rocket::build()
-        .manage(pool)
-        .mount("/", rocket::routes!(create, signin, test_route, refresh))
+    // The store is kept to close its pool once the server has stopped
+    let users = stores.users.clone();
+
+    // Rocket stops on SIGTERM or SIGINT, and only returns once in-flight requests have finished or the shutdown grace period has passed
+    let launched = with_shutdown_config(build_rocket(stores))
         .launch()
-        .await?;
+        .await;
+
+    users.close().await;
+    rocket::info!("Database connections closed");
+
+    // Sending the spans still waiting in the batch before exiting
+    if let Some(provider) = tracer_provider && let Err(e) = provider.shutdown() {
+        eprintln!("Failed to flush traces: {}", e);
+    }
+
+    let _rocket = launched.map_err(Box::new)?;
 
     Ok(())
-}
\ No newline at end of file
+}
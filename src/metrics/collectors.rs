@@ -0,0 +1,143 @@
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder
+};
+
+use crate::db::store::PoolStatus;
+
+/// The metrics exported on /metrics
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The counters and histograms of the server, kept in their own registry
+pub struct Metrics {
+    registry: Registry,
+    pub signin_successes: IntCounter,
+    /// Labelled with the error code returned, such as incorrect_password
+    pub signin_failures: IntCounterVec,
+    pub accounts_created: IntCounter,
+    /// Labelled with success, reuse when a rotated refresh token is presented again, or why the refresh was refused: missing, invalid, expired or account_disabled
+    pub refreshes: IntCounterVec,
+    /// Tokens refused by the AuthUser guard, labelled with the reason
+    pub token_validation_failures: IntCounterVec,
+    /// Labelled with hash or verify
+    pub password_hashing: HistogramVec,
+    /// Labelled with the store query, such as read_user
+    pub db_queries: HistogramVec,
+    /// Labelled with active or idle
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_max_connections: IntGauge,
+    pub rate_limited: IntCounter
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let metrics = Self {
+            signin_successes: IntCounter::new("auth_signin_successes_total", "Successful sign-ins").unwrap(),
+            signin_failures: IntCounterVec::new(
+                Opts::new("auth_signin_failures_total", "Failed sign-ins by reason"), &["reason"]
+            ).unwrap(),
+            accounts_created: IntCounter::new("auth_accounts_created_total", "Accounts created").unwrap(),
+            refreshes: IntCounterVec::new(
+                Opts::new("auth_refreshes_total", "Access token refreshes by result"), &["result"]
+            ).unwrap(),
+            token_validation_failures: IntCounterVec::new(
+                Opts::new("auth_token_validation_failures_total", "Access tokens refused by the AuthUser guard by reason"), &["reason"]
+            ).unwrap(),
+            // Argon2 with the default settings takes tens of milliseconds, so the buckets go from 1ms to about 4s
+            password_hashing: HistogramVec::new(
+                HistogramOpts::new("auth_password_hash_duration_seconds", "Time spent hashing and verifying passwords")
+                    .buckets(exponential_buckets(0.001, 2.0, 13).unwrap()),
+                &["operation"]
+            ).unwrap(),
+            db_queries: HistogramVec::new(
+                HistogramOpts::new("auth_db_query_duration_seconds", "Time spent on database queries by query"), &["query"]
+            ).unwrap(),
+            db_pool_connections: IntGaugeVec::new(
+                Opts::new("auth_db_pool_connections", "Open database connections by state"), &["state"]
+            ).unwrap(),
+            db_pool_max_connections: IntGauge::new("auth_db_pool_max_connections", "The most connections the database pool will open").unwrap(),
+            rate_limited: IntCounter::new("auth_rate_limited_requests_total", "Requests rejected with 429 Too Many Requests").unwrap(),
+            registry
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(metrics.signin_successes.clone()),
+            Box::new(metrics.signin_failures.clone()),
+            Box::new(metrics.accounts_created.clone()),
+            Box::new(metrics.refreshes.clone()),
+            Box::new(metrics.token_validation_failures.clone()),
+            Box::new(metrics.password_hashing.clone()),
+            Box::new(metrics.db_queries.clone()),
+            Box::new(metrics.db_pool_connections.clone()),
+            Box::new(metrics.db_pool_max_connections.clone()),
+            Box::new(metrics.rate_limited.clone())
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("Metric registered twice");
+        }
+
+        metrics
+    }
+
+    /// Sets the database pool gauges
+    ///
+    /// # Arguments
+    /// - `status`: The connections of the pool, or None if the store has no pool
+    pub fn set_pool_status(&self, status: Option<PoolStatus>) {
+        let Some(status) = status else {
+            return;
+        };
+
+        self.db_pool_connections.with_label_values(&["active"]).set(status.size.saturating_sub(status.idle) as i64);
+        self.db_pool_connections.with_label_values(&["idle"]).set(status.idle as i64);
+        self.db_pool_max_connections.set(status.max as i64);
+    }
+
+    /// Writes every metric in the Prometheus text format
+    ///
+    /// # Returns
+    /// The metrics, or an error if they could not be encoded
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Runs a database query, recording how long it took
+///
+/// # Arguments
+/// - `query`: The name the query is recorded under
+/// - `future`: The query
+///
+/// # Returns
+/// The result of the query
+pub async fn timed<F: Future>(query: &str, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    METRICS.db_queries.with_label_values(&[query]).observe(start.elapsed().as_secs_f64());
+
+    output
+}
+
+/// Runs a password hash or verification, recording how long it took
+///
+/// # Arguments
+/// - `operation`: hash or verify
+/// - `f`: The hashing work
+///
+/// # Returns
+/// The result of the work
+pub fn time_hashing<T>(operation: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let output = f();
+    METRICS.password_hashing.with_label_values(&[operation]).observe(start.elapsed().as_secs_f64());
+
+    output
+}
@@ -0,0 +1,21 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::{Request, Response};
+
+use crate::metrics::collectors::METRICS;
+
+/// Counts the responses that are only visible once a request has finished, such as rate-limit rejections from any route or guard
+pub struct MetricsFairing;
+
+#[rocket::async_trait]
+impl Fairing for MetricsFairing {
+    fn info(&self) -> Info {
+        Info { name: "Metrics", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
+        if response.status() == Status::TooManyRequests {
+            METRICS.rate_limited.inc();
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod collectors;
+pub mod fairing;
\ No newline at end of file
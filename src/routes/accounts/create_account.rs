@@ -1,69 +1,50 @@
 use rocket::serde::json::Json;
-use crate::db::create_user::write_user;
 
 use rocket::State;
 
 use rocket::http::Status;
 use crate::db::user::{UserArgs, AuthUser};
 
-use sqlx::PgPool;
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynUserStore, StoreError};
 
 use crate::crypto::hash::PasswordHash;
+use crate::errors::auth_error::AuthError;
+use crate::metrics::collectors::METRICS;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::responses::UserInfo;
 
-const INTERNAL_ERROR: &str = "An internal server error has occured";
-const DATABASE_CONFLICT_CODE: &str = "23505";
-
-/// Create account route to create a user account; this function hashes the password and stores the data in a PostegreSQL database. This route must be entered with valid JSON data following the structure of the Users struct in the Users file.
+/// Create account route to create a user account; this function hashes the password and stores the data in the user store. This route must be entered with valid JSON data following the structure of the Users struct in the Users file.
 ///
 /// # Arguments
 /// - `user`: A struct containing the user data sent in the request. This contains a username and password
-/// - `pool`: A reference to a State<PgPool> which is required for connecting to an SQL database
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
 ///
 /// # Returns
-/// Returns a tuple containing a rocket http status code and a string reference to be returned to the client
+/// Returns the created user with a 201 status, or an AuthError
 #[rocket::post("/create", format = "json", data="<user>")]
-pub async fn create(user: Json<UserArgs>, pool: &State<PgPool>) -> (Status, &'static str) {
-
-    let Ok(hashed_password) = PasswordHash::try_from(user.password.as_str()) else {
-        return (Status::InternalServerError, INTERNAL_ERROR);
-    };
-    match PasswordHash::try_from(user.password.as_str()) {
-        Ok(hashed_password) => {
-            match write_user(&UserArgs {
-                username: user.username.clone(),
-                password: hashed_password.value()
-            }, pool.inner()).await {
-
-                Ok(_) => (Status::Created, "Account created successfully"),
-
-                // For database errors
-                Err(sqlx::error::Error::Database(e)) => {
-
-                    let db_error = e.downcast_ref::<sqlx::postgres::PgDatabaseError>();
-
-                    if db_error.code() == DATABASE_CONFLICT_CODE {
-                        (Status::Conflict, "User already exists")
-                    } else {
-                        rocket::error!("{}", e);
-                        (Status::InternalServerError, INTERNAL_ERROR)
-                    }
-                },
-
-                // For generic errors
-                Err(e) => {
-                    rocket::error!("{}", e);
-                    (Status::InternalServerError, INTERNAL_ERROR)
-                }
-            }
-        },
-        Err(e) => {
-            rocket::error!("{}", e);
-            (Status::InternalServerError, INTERNAL_ERROR)
-        }
-    }
+pub async fn create(user: Json<UserArgs>, client: ClientInfo, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<(Status, Json<UserInfo>), AuthError> {
+    let hashed_password = PasswordHash::try_from(user.password.as_str())?;
+
+    let user_id = users.write_user(&UserArgs {
+        username: user.username.clone(),
+        password: hashed_password.value()
+    }).await.map_err(|e| match e {
+        StoreError::Conflict => AuthError::UserExists,
+        e => AuthError::from(e)
+    })?;
+    record_event(events, EventType::Signup, Some(user_id), &client).await;
+    METRICS.accounts_created.inc();
+
+    Ok((Status::Created, Json(UserInfo {
+        user_id,
+        username: user.username.clone()
+    })))
 }
 
 #[rocket::get("/test")]
 pub fn test_route(user: AuthUser) -> String {
     format!("Hello there: {}, UserID: {}", user.username, user.user_id)
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::State;
+use chrono::{TimeZone, Utc};
+
+use crate::crypto::create_refresh::RefreshConfig;
+use crate::crypto::csrf::CsrfGuard;
+use crate::crypto::session_cookies::remove_session_cookies;
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynSessionStore};
+use crate::routes::accounts::refresh_token::RefreshUser;
+use crate::errors::auth_error::AuthError;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::responses::MessageResponse;
+
+/// Logout route, revokes the refresh session the JWT was issued for, adds the JWT to the denylist so AuthUser refuses it straight away, and removes the session cookies. Expired JWTs are accepted so a user can always log out. In browser session mode the request must carry the CSRF token in the X-CSRF-Token header.
+///
+/// # Arguments
+/// - `user`: A RefreshUser struct containing the user_id, username and session
+/// - `_csrf`: The CSRF request guard
+/// - `client`: The client details recorded in the audit log
+/// - `jar`: A reference to the cookie jar provided by rocket
+/// - `sessions`: The refresh session store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns a MessageResponse, or an AuthError
+#[rocket::post("/logout")]
+pub async fn logout(user: RefreshUser, _csrf: CsrfGuard, client: ClientInfo, jar: &CookieJar<'_>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<Json<MessageResponse>, AuthError> {
+    if let Some(session_id) = user.session_id {
+        sessions.revoke_refresh_session(session_id).await?;
+    }
+    // An expired JWT is already refused, so only one still in use is added to the denylist
+    if let (Some(jti), Some(expires_at)) = (user.jti, Utc.timestamp_opt(user.exp, 0).single()) && expires_at > Utc::now() {
+        sessions.revoke_access_token(jti, expires_at).await?;
+    }
+    record_event(events, EventType::Logout, Some(user.user_id), &client).await;
+
+    let refresh_config = RefreshConfig::from_env();
+    jar.remove_private(refresh_config.cookie(String::new(), Utc::now()));
+    remove_session_cookies(jar, &refresh_config);
+
+    Ok(MessageResponse::new("Logged out"))
+}
@@ -1,3 +1,6 @@
 pub mod create_account;
 pub mod signin;
-pub mod refresh_token;
\ No newline at end of file
+pub mod refresh_token;
+pub mod logout;
+pub mod profile;
+pub mod security_events;
\ No newline at end of file
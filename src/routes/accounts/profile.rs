@@ -0,0 +1,293 @@
+use std::sync::OnceLock;
+
+use chrono::{Duration, Utc};
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use rocket::serde::{Deserialize, Deserializer};
+use rocket::State;
+
+use crate::config::env_vars::env_or;
+use crate::crypto::create_refresh::{random_token, RefreshConfig};
+use crate::crypto::csrf::CsrfGuard;
+use crate::crypto::hash::PasswordHash;
+use crate::crypto::session_cookies::remove_session_cookies;
+use crate::crypto::token_hash::{hash_email_token, verify_email_token};
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynSessionStore, DynUserStore, StoreError};
+use crate::db::user::{AuthUser, User};
+use crate::errors::auth_error::AuthError;
+use crate::mail::mailer::DynMailer;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::responses::{AccountExport, ExportDownload, MessageResponse, ProfileResponse};
+
+const MAX_FIELD_LENGTH: usize = 255;
+
+/// How long an email verification code is accepted for, read from EMAIL_VERIFICATION_LIFETIME (in seconds, default a day) on first use. The server exits if it is out of range
+fn email_verification_lifetime() -> Duration {
+    static LIFETIME: OnceLock<Duration> = OnceLock::new();
+
+    *LIFETIME.get_or_init(|| {
+        let lifetime = Duration::try_seconds(env_or("EMAIL_VERIFICATION_LIFETIME", 86400))
+            .filter(|lifetime| Utc::now().checked_add_signed(*lifetime).is_some());
+        match lifetime {
+            Some(lifetime) => lifetime,
+            None => {
+                eprintln!("EMAIL_VERIFICATION_LIFETIME is out of range in .env");
+                std::process::exit(1)
+            }
+        }
+    })
+}
+
+/// The fields of PATCH /me. A field left out is not changed, and a field set to null is removed
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct ProfileUpdate {
+    #[serde(default, deserialize_with = "present")]
+    pub display_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<Option<String>>
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct UsernameChange {
+    pub username: String
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct EmailVerification {
+    pub token: String
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct PasswordConfirmation {
+    pub password: String
+}
+
+/// Shows the signed in user's profile
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `users`: The user store managed by rocket
+///
+/// # Returns
+/// Returns the ProfileResponse, or an AuthError
+#[rocket::get("/me")]
+pub async fn get_profile(user: AuthUser, users: &State<DynUserStore>) -> Result<Json<ProfileResponse>, AuthError> {
+    let account = find_account(&user, users).await?;
+    Ok(Json(ProfileResponse::from(account)))
+}
+
+/// Updates the display name and email address of the signed in user. A new email address is unverified until the code emailed to it is sent to POST /me/email/verify
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `_csrf`: The CSRF request guard
+/// - `update`: The fields to change
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `mailer`: The mailer managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the updated ProfileResponse, or an AuthError
+#[rocket::patch("/me", format = "json", data = "<update>")]
+pub async fn update_profile(user: AuthUser, _csrf: CsrfGuard, update: Json<ProfileUpdate>, client: ClientInfo, users: &State<DynUserStore>, mailer: &State<DynMailer>, events: &State<DynEventStore>) -> Result<Json<ProfileResponse>, AuthError> {
+    let account = find_account(&user, users).await?;
+    let update = update.into_inner();
+
+    // Every field is checked before anything is written, so a refused update changes nothing
+    let display_name = update.display_name.map(|name| name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty()));
+    if let Some(display_name) = &display_name {
+        check_length("display_name", display_name.as_deref())?;
+    }
+
+    let email = update.email.map(|email| email.map(|email| email.trim().to_string()).filter(|email| !email.is_empty()));
+    if let Some(email) = &email {
+        check_length("email", email.as_deref())?;
+        if let Some(email) = email && !is_email(email) {
+            return Err(AuthError::Validation("email is not a valid email address".to_string()));
+        }
+    }
+
+    // The code is emailed before the new address is saved, so an email that cannot be sent leaves the account as it was
+    let email_change = match email {
+        // Setting the address it already has keeps it verified
+        Some(Some(email)) if account.email.as_deref() == Some(email.as_str()) => None,
+        Some(Some(email)) => {
+            let token = random_token()?;
+            let expires_at = Utc::now() + email_verification_lifetime();
+            mailer.send(
+                &email,
+                "Verify your email address",
+                &format!("Your email verification code is {}. It expires at {}.", token, expires_at.to_rfc2822())
+            ).await?;
+
+            Some((Some(email), Some(hash_email_token(&token)), Some(expires_at)))
+        },
+        Some(None) => Some((None, None, None)),
+        None => None
+    };
+
+    if let Some(display_name) = display_name {
+        users.set_display_name(account.user_id, display_name.as_deref()).await?;
+    }
+    if let Some((email, token_hash, expires_at)) = email_change {
+        users.set_email(account.user_id, email.as_deref(), token_hash.as_deref(), expires_at).await?;
+        record_event(events, EventType::EmailChange, Some(account.user_id), &client).await;
+    }
+
+    let account = find_account(&user, users).await?;
+    Ok(Json(ProfileResponse::from(account)))
+}
+
+/// Verifies the signed in user's email address with the code that was emailed to it
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `_csrf`: The CSRF request guard
+/// - `verification`: The verification code
+/// - `users`: The user store managed by rocket
+///
+/// # Returns
+/// Returns the updated ProfileResponse, or an AuthError
+#[rocket::post("/me/email/verify", format = "json", data = "<verification>")]
+pub async fn verify_email(user: AuthUser, _csrf: CsrfGuard, verification: Json<EmailVerification>, users: &State<DynUserStore>) -> Result<Json<ProfileResponse>, AuthError> {
+    let account = find_account(&user, users).await?;
+
+    let is_valid = match (&account.email_token_hash, account.email_token_expires_at) {
+        (Some(token_hash), Some(expires_at)) => expires_at > Utc::now() && verify_email_token(&verification.token, token_hash),
+        _ => false
+    };
+    if !is_valid {
+        return Err(AuthError::InvalidEmailToken);
+    }
+
+    users.mark_email_verified(account.user_id).await?;
+
+    let account = find_account(&user, users).await?;
+    Ok(Json(ProfileResponse::from(account)))
+}
+
+/// Changes the signed in user's username. The current JWT keeps the old username until it is refreshed
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `_csrf`: The CSRF request guard
+/// - `change`: The new username
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the updated ProfileResponse, or an AuthError
+#[rocket::put("/me/username", format = "json", data = "<change>")]
+pub async fn change_username(user: AuthUser, _csrf: CsrfGuard, change: Json<UsernameChange>, client: ClientInfo, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<Json<ProfileResponse>, AuthError> {
+    let account = find_account(&user, users).await?;
+
+    let username = change.username.trim();
+    if username.is_empty() {
+        return Err(AuthError::Validation("username cannot be empty".to_string()));
+    }
+    check_length("username", Some(username))?;
+
+    users.update_username(account.user_id, username).await.map_err(|e| match e {
+        StoreError::Conflict => AuthError::UserExists,
+        e => AuthError::from(e)
+    })?;
+    record_event(events, EventType::UsernameChange, Some(account.user_id), &client).await;
+
+    let account = find_account(&user, users).await?;
+    Ok(Json(ProfileResponse::from(account)))
+}
+
+/// Exports everything stored about the signed in user as a JSON file: their account, roles, refresh tokens and security events
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The session store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the AccountExport as a download, or an AuthError
+#[rocket::get("/me/export")]
+pub async fn export_account(user: AuthUser, users: &State<DynUserStore>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<ExportDownload, AuthError> {
+    let account = find_account(&user, users).await?;
+    let roles = users.get_roles(account.user_id).await?;
+    let refresh_tokens = sessions.list_refresh_tokens(account.user_id).await?;
+    let security_events = events.list_events(Some(account.user_id), i64::MAX, 0).await?;
+
+    Ok(ExportDownload::from(AccountExport {
+        exported_at: Utc::now(),
+        roles,
+        refresh_tokens: refresh_tokens.into_iter().map(Into::into).collect(),
+        security_events,
+        account: account.into()
+    }))
+}
+
+/// Deletes the signed in user's account once their password is confirmed, revoking their refresh sessions and removing the session cookies. The account is kept, hidden, for DELETED_USER_GRACE_PERIOD before it is removed for good
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `_csrf`: The CSRF request guard
+/// - `confirmation`: The user's current password
+/// - `client`: The client details recorded in the audit log
+/// - `jar`: A reference to the cookie jar provided by rocket
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns a MessageResponse, or an AuthError
+#[rocket::delete("/me", format = "json", data = "<confirmation>")]
+pub async fn delete_account(user: AuthUser, _csrf: CsrfGuard, confirmation: Json<PasswordConfirmation>, client: ClientInfo, jar: &CookieJar<'_>, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<Json<MessageResponse>, AuthError> {
+    let account = find_account(&user, users).await?;
+
+    if !PasswordHash::from(account.password.clone()).verify(&confirmation.password)? {
+        return Err(AuthError::IncorrectPassword);
+    }
+
+    users.soft_delete_user(account.user_id).await?;
+    record_event(events, EventType::AccountDeleted, Some(account.user_id), &client).await;
+
+    let refresh_config = RefreshConfig::from_env();
+    jar.remove_private(refresh_config.cookie(String::new(), Utc::now()));
+    remove_session_cookies(jar, &refresh_config);
+
+    Ok(MessageResponse::new("Account deleted"))
+}
+
+/// Loads the account of the signed in user, which may have been deleted since the JWT was issued
+async fn find_account(user: &AuthUser, users: &State<DynUserStore>) -> Result<User, AuthError> {
+    users.read_user_by_id(user.user_id as i64).await?.ok_or(AuthError::AccountNotFound)
+}
+
+fn check_length(field: &str, value: Option<&str>) -> Result<(), AuthError> {
+    match value {
+        Some(value) if value.chars().count() > MAX_FIELD_LENGTH => {
+            Err(AuthError::Validation(format!("{} must be at most {} characters", field, MAX_FIELD_LENGTH)))
+        },
+        _ => Ok(())
+    }
+}
+
+/// A light check that catches typos; the verification email is what proves the address works
+fn is_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.') && !email.contains(char::is_whitespace),
+        None => false
+    }
+}
+
+/// Deserializes a field that is present, even as null, into Some so it can be told apart from a missing field
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -1,91 +1,142 @@
 use rocket::request::{FromRequest, Outcome};
 use rocket::{Request, http::Status, http::CookieJar};
 
-use crate::db::get_refresh_entry::get_refresh_entry;
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynSessionStore, DynUserStore};
+use crate::crypto::create_refresh::{create_refresh, RefreshConfig};
+use crate::crypto::csrf::CsrfGuard;
 use crate::crypto::jwt::{JwtStatus, decode_jwt, create_jwt};
-use crate::crypto::hash::PasswordHash;
+use crate::crypto::token_hash::{hash_refresh_token, verify_refresh_token};
+use crate::crypto::session_cookies::{access_token, add_session_cookies, cookie_mode, remove_session_cookies};
+use crate::errors::auth_error::AuthError;
+use crate::metrics::collectors::METRICS;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::responses::{TokenResponse, UserInfo};
 
-use sqlx::PgPool;
+use chrono::Utc;
 use rocket::State;
+use rocket::serde::json::Json;
+use uuid::Uuid;
 
+/// Request guard for routes that accept an expired JWT, such as refresh and logout
 #[derive(Debug)]
 pub struct RefreshUser {
     pub user_id: i64,
-    pub username: String
+    pub username: String,
+    pub session_id: Option<Uuid>,
+    /// The JWT's id, so logout can add it to the denylist
+    pub jti: Option<Uuid>,
+    /// When the JWT expires, in seconds since the Unix epoch
+    pub exp: i64
 }
 
-const INTERNAL_ERROR: &str = "An internal server error has occured";
-
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for RefreshUser {
     type Error = ();
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        match request.headers().get_one("Authorization") {
-            Some(header) => {
-                let Some(key) = header.strip_prefix("Bearer ") else {
-                    return Outcome::Forward(Status::BadRequest);
-                };
-                match decode_jwt(key) {
-                    Ok(JwtStatus::Expired(user)) => {
-                        Outcome::Success(RefreshUser {
-                            user_id: user.user_id,
-                            username: user.username
-                        })
-                    },
-                    Ok(JwtStatus::Valid(user)) => {
-                        Outcome::Success(RefreshUser {
-                            user_id: user.user_id,
-                            username: user.username
-                        })
-                    },
-                    _ => Outcome::Error((Status::Unauthorized, ()))
-                }
+        let key = match access_token(request) {
+            Ok(key) => key,
+            Err(status) => return Outcome::Forward(status)
+        };
+
+        match decode_jwt(&key) {
+            Ok(JwtStatus::Expired(user)) | Ok(JwtStatus::Valid(user)) => {
+                Outcome::Success(RefreshUser {
+                    user_id: user.user_id,
+                    username: user.username,
+                    session_id: user.session_id,
+                    jti: user.jti,
+                    exp: user.exp
+                })
             },
-            None => Outcome::Forward(Status::Unauthorized)
+            _ => Outcome::Error((Status::Unauthorized, ()))
         }
     }
 }
 
-/// Refresh JWT Route, to remake a JWT token from an expired JWT Token, The request must contain the valid refresh token in the HTTP-Only Cookies.
+/// Refresh JWT Route, to remake a JWT token from an expired JWT Token, The request must contain the valid refresh token in the HTTP-Only Cookies and a JWT from the same session. In browser session mode the request must carry the CSRF token in the X-CSRF-Token header. Each refresh replaces the refresh token cookie with a new one, and presenting a token that has already been replaced revokes the whole session as it may have been stolen.
 ///
 /// # Arguments
 /// - `user`: A RefreshUser struct containing the user_id and the username
+/// - `_csrf`: The CSRF request guard
+/// - `client`: The client details recorded in the audit log
 /// - `jar`: A reference to the cookie jar provided by rocket
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+/// - `events`: The audit log managed by rocket
 ///
 /// # Returns
-/// Returns a tuple containing a rocket http status and a String containing the new JWT token or an error message
-#[rocket::get("/refresh")]
-pub async fn refresh(user: RefreshUser, jar: &CookieJar<'_>, pool: &State<PgPool>) -> (Status, String) {
-    match get_refresh_entry(user.user_id, pool.inner()).await {
-        Ok(token) => {
-            let Some(cookie) = jar.get_private("refresh_token") else {
-                return (Status::Unauthorized, "No valid refresh token".to_string());
-            };
-
-            let hash_refresh_token = PasswordHash::from(token.refresh_token);
-
-            match hash_refresh_token.verify(&cookie.value().to_string()) {
-                Ok(is_token_valid) => {
-                    if is_token_valid {
-                        return (Status::Unauthorized, "Refresh token is invalid".to_string());
-                    }
-
-                    let token = create_jwt(user.username, user.user_id);
-                    (Status::Ok, format!("AuthToken: \"{}\"", token))
-                },
-                Err(e) => {
-                    rocket::error!("{}", e);
-                    return (Status::InternalServerError, INTERNAL_ERROR.to_string());
-                }
-            }
-        },
-        Err(sqlx::error::Error::RowNotFound) => {
-            (Status::NotFound, "User doesn't exist".to_string())
-        },
-        Err(e) => {
-            rocket::error!("{}", e);
-            (Status::InternalServerError, INTERNAL_ERROR.to_string())
+/// Returns a TokenResponse with the new JWT, or an AuthError
+#[rocket::post("/refresh")]
+pub async fn refresh(user: RefreshUser, _csrf: CsrfGuard, client: ClientInfo, jar: &CookieJar<'_>, users: &State<DynUserStore>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<Json<TokenResponse>, AuthError> {
+    let refresh_config = RefreshConfig::from_env();
+    let now = Utc::now();
+
+    let Some(cookie) = jar.get_private(&refresh_config.cookie_name) else {
+        METRICS.refreshes.with_label_values(&["missing"]).inc();
+        return Err(AuthError::MissingRefreshToken);
+    };
+
+    let entry = match sessions.get_refresh_entry(&hash_refresh_token(cookie.value())).await? {
+        // The JWT must be the one issued for the refresh token's session, not just any token of the same user
+        Some(entry) if entry.user_id == user.user_id && user.session_id == Some(entry.session_id)
+            && verify_refresh_token(cookie.value(), &entry.token_hash) && entry.revoked_at.is_none() => entry,
+        _ => {
+            METRICS.refreshes.with_label_values(&["invalid"]).inc();
+            return Err(AuthError::InvalidRefreshToken);
         }
+    };
+
+    if entry.expires_at <= now {
+        METRICS.refreshes.with_label_values(&["expired"]).inc();
+        return Err(AuthError::InvalidRefreshToken);
     }
-}
\ No newline at end of file
+
+    // The account is loaded again so the new JWT carries the current username, and deleted or disabled accounts are turned away
+    let Some(account) = users.read_user_by_id(user.user_id).await? else {
+        METRICS.refreshes.with_label_values(&["invalid"]).inc();
+        return Err(AuthError::InvalidRefreshToken);
+    };
+    if account.disabled_at.is_some() {
+        METRICS.refreshes.with_label_values(&["account_disabled"]).inc();
+        return Err(AuthError::AccountDisabled);
+    }
+
+    let new_expires_at = refresh_config.rotated_expiry(entry.expires_at, now);
+    let (new_cookie, new_hash) = create_refresh(&refresh_config, new_expires_at)?;
+
+    // A token that was already rotated is being used again, so either the client or an attacker holds a stolen copy
+    let is_rotated = match entry.rotated_at {
+        Some(_) => false,
+        None => sessions.rotate_refresh_entry(&entry, &new_hash, new_expires_at).await?
+    };
+
+    if !is_rotated {
+        rocket::warn!("Refresh token reuse detected for user {}, revoking session {}", entry.user_id, entry.session_id);
+        sessions.revoke_refresh_session(entry.session_id).await?;
+        record_event(events, EventType::RefreshReuse, Some(entry.user_id), &client).await;
+        METRICS.refreshes.with_label_values(&["reuse"]).inc();
+        jar.remove_private(refresh_config.cookie(String::new(), now));
+        remove_session_cookies(jar, &refresh_config);
+        return Err(AuthError::InvalidRefreshToken);
+    }
+
+    jar.add_private(new_cookie);
+    record_event(events, EventType::Refresh, Some(account.user_id), &client).await;
+    METRICS.refreshes.with_label_values(&["success"]).inc();
+
+    let roles = users.get_roles(account.user_id).await?;
+    let token = create_jwt(account.username.clone(), account.user_id, entry.session_id, &roles);
+    let user_info = UserInfo {
+        user_id: account.user_id,
+        username: account.username
+    };
+
+    if cookie_mode() {
+        add_session_cookies(jar, &refresh_config, token, new_expires_at)?;
+        return Ok(TokenResponse::new(None, (new_expires_at - now).num_seconds(), user_info));
+    }
+
+    Ok(TokenResponse::new(Some(token), (new_expires_at - now).num_seconds(), user_info))
+}
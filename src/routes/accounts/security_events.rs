@@ -0,0 +1,24 @@
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::db::store::DynEventStore;
+use crate::db::user::AuthUser;
+use crate::errors::auth_error::AuthError;
+use crate::routes::audit::event_page;
+use crate::routes::pagination::Pagination;
+use crate::routes::responses::EventPage;
+
+/// Lists the signed in user's authentication events a page at a time, newest first, so they can spot sign-ins they do not recognise
+///
+/// # Arguments
+/// - `user`: An AuthUser struct containing the user_id and username
+/// - `page`: The page number, starting at 1
+/// - `per_page`: The number of events per page, at most 100
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns an EventPage, or an AuthError
+#[rocket::get("/me/security-events?<page>&<per_page>")]
+pub async fn list_security_events(user: AuthUser, page: Option<i64>, per_page: Option<i64>, events: &State<DynEventStore>) -> Result<Json<EventPage>, AuthError> {
+    event_page(Some(user.user_id as i64), Pagination::from_query(page, per_page)?, events).await
+}
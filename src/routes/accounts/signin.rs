@@ -1,94 +1,108 @@
 use rocket::serde::json::Json;
 
-use rocket::http::{Status, CookieJar};
+use rocket::http::CookieJar;
 
 use crate::db::user::UserArgs;
 use crate::crypto::jwt::create_jwt;
 
-use crate::db::get_user::read_user;
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynSessionStore, DynUserStore};
 
-
-use sqlx::PgPool;
 use rocket::State;
+use uuid::Uuid;
+use chrono::Utc;
 
-use crate::db::create_refresh_entry::{create_refresh_entry, RefreshToken};
 use crate::crypto::hash::PasswordHash;
 
-use crate::crypto::create_refresh::create_refresh;
-
-const INTERNAL_ERROR: &str = "An internal server error has occured";
+use crate::crypto::create_refresh::{create_refresh, RefreshConfig};
+use crate::crypto::session_cookies::{add_session_cookies, cookie_mode};
+use crate::errors::auth_error::AuthError;
+use crate::metrics::collectors::METRICS;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::responses::{TokenResponse, UserInfo};
 
 /// Sign-in route to login a user into their account; finds the account with matching credentials and returns a JWT and refresh token for future authentication into protected routes. This route must be entered with json data following the User struct.
 ///
 /// args
 /// user: A struct containing the user data sent in the request. This contains a username and password
+/// client: The client details recorded in the audit log
 /// jar: A reference to the cookie jar provided by rocket
+/// users: The user store managed by rocket
+/// sessions: The refresh session store managed by rocket
+/// events: The audit log managed by rocket
 ///
 /// returns
-/// Returns a tuple containing a rocket http status and a string
+/// Returns a TokenResponse with the JWT and the signed in user, or an AuthError
 
 #[rocket::post("/signin", format = "json", data="<user>")]
-pub async fn signin(user: Json<UserArgs>, jar: &CookieJar<'_>, pool: &State<PgPool>) -> (Status, String) {
-
-    match read_user(user.username.as_str(), pool.inner()).await {
-        Ok(users) => {
-            let num_matched_users = users.len();
-
-            if num_matched_users >= 2 {
-                // too many accounts
-                rocket::error!("Multiple accounts found with the same usernameio ");
-                (Status::InternalServerError, "An error with this account has occured, please contact support".to_string())
-            } else if num_matched_users == 1 {
-                let matched_user = &users[0];
-
-                // Verifing password
-                let input_password = &matched_user.password;
-                let hash = PasswordHash::from(input_password.clone());
-
-                match hash.verify(&user.password) {
-                    Ok(is_password_valid) => {
-                        if !is_password_valid {
-                            return (Status::Unauthorized, "Password is incorrect".to_string());
-                        }
-
-                        match create_refresh() {
-                            Ok((cookie, refresh_token)) => {
-                                jar.add_private(cookie);
-
-                                match create_refresh_entry(RefreshToken {
-                                    user_id: matched_user.user_id,
-                                    refresh_token: refresh_token
-                                }, pool.inner()).await {
-                                    Ok(_) => {
-                                        // Creating JWT
-                                        let token = create_jwt(matched_user.username.clone(), matched_user.user_id);
-                                        (Status::Ok, format!("AuthToken: \"{}\"", token))
-                                    },
-                                    Err(e) => {
-                                        rocket::error!("{}", e);
-                                        (Status::InternalServerError, INTERNAL_ERROR.to_string())
-                                    }
-                                }
-                            },
-                            Err(e) => {
-                                rocket::error!("{:?}", e);
-                                (Status::InternalServerError, INTERNAL_ERROR.to_string())
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        rocket::error!("{}", e);
-                        (Status::InternalServerError, INTERNAL_ERROR.to_string())
-                    }
-                }
-            } else {
-                // no account
-                (Status::NotFound, "No account found by that username".to_string())
-            }
+pub async fn signin(user: Json<UserArgs>, client: ClientInfo, jar: &CookieJar<'_>, users: &State<DynUserStore>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<Json<TokenResponse>, AuthError> {
+    let matched_users = users.read_user(user.username.as_str()).await?;
+
+    let matched_user = match matched_users.as_slice() {
+        [matched_user] => matched_user,
+        // no account
+        [] => {
+            record_event(events, EventType::SigninFailure, None, &client).await;
+            METRICS.signin_failures.with_label_values(&["account_not_found"]).inc();
+            return Err(AuthError::AccountNotFound);
+        },
+        // too many accounts
+        _ => {
+            rocket::error!("Multiple accounts found with the same username");
+            METRICS.signin_failures.with_label_values(&["account_conflict"]).inc();
+            return Err(AuthError::AccountConflict);
         }
-        Err(e) => {
-            rocket::error!("{}", e);
-            (Status::InternalServerError, INTERNAL_ERROR.to_string())
+    };
+
+    // Verifing password
+    let hash = PasswordHash::from(matched_user.password.clone());
+    if !hash.verify(&user.password)? {
+        record_event(events, EventType::SigninFailure, Some(matched_user.user_id), &client).await;
+        METRICS.signin_failures.with_label_values(&["incorrect_password"]).inc();
+        return Err(AuthError::IncorrectPassword);
+    }
+
+    if matched_user.disabled_at.is_some() {
+        record_event(events, EventType::SigninFailure, Some(matched_user.user_id), &client).await;
+        METRICS.signin_failures.with_label_values(&["account_disabled"]).inc();
+        return Err(AuthError::AccountDisabled);
+    }
+
+    // Upgrading hashes made with outdated Argon2 settings, failures here do not block the sign-in
+    if hash.needs_rehash() {
+        match PasswordHash::try_from(user.password.as_str()) {
+            Ok(new_hash) => {
+                if let Err(e) = users.update_password(matched_user.user_id, &new_hash.value()).await {
+                    rocket::error!("{}", e);
+                }
+            },
+            Err(e) => rocket::error!("{}", e)
         }
     }
-}
\ No newline at end of file
+
+    let refresh_config = RefreshConfig::from_env();
+    let expires_at = Utc::now() + refresh_config.lifetime;
+    let (cookie, refresh_hash) = create_refresh(&refresh_config, expires_at)?;
+
+    // Every sign-in starts a new refresh session
+    let session_id = Uuid::new_v4();
+    sessions.create_refresh_entry(matched_user.user_id, session_id, &refresh_hash, expires_at).await?;
+    jar.add_private(cookie);
+    record_event(events, EventType::SigninSuccess, Some(matched_user.user_id), &client).await;
+    METRICS.signin_successes.inc();
+
+    // Creating JWT, with the user's roles for services that check them
+    let roles = users.get_roles(matched_user.user_id).await?;
+    let token = create_jwt(matched_user.username.clone(), matched_user.user_id, session_id, &roles);
+    let user_info = UserInfo {
+        user_id: matched_user.user_id,
+        username: matched_user.username.clone()
+    };
+
+    if cookie_mode() {
+        add_session_cookies(jar, &refresh_config, token, expires_at)?;
+        return Ok(TokenResponse::new(None, refresh_config.lifetime.num_seconds(), user_info));
+    }
+
+    Ok(TokenResponse::new(Some(token), refresh_config.lifetime.num_seconds(), user_info))
+}
@@ -0,0 +1,3 @@
+pub mod users;
+pub mod sessions;
+pub mod security_events;
\ No newline at end of file
@@ -0,0 +1,45 @@
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::db::store::{DynEventStore, DynUserStore};
+use crate::db::user::AdminUser;
+use crate::errors::auth_error::AuthError;
+use crate::routes::admin::users::find_user;
+use crate::routes::audit::event_page;
+use crate::routes::pagination::Pagination;
+use crate::routes::responses::EventPage;
+
+/// Lists every authentication event a page at a time, newest first, including failed sign-ins to accounts that do not exist
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `page`: The page number, starting at 1
+/// - `per_page`: The number of events per page, at most 100
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns an EventPage, or an AuthError
+#[rocket::get("/security-events?<page>&<per_page>")]
+pub async fn list_all_events(_admin: AdminUser, page: Option<i64>, per_page: Option<i64>, events: &State<DynEventStore>) -> Result<Json<EventPage>, AuthError> {
+    event_page(None, Pagination::from_query(page, per_page)?, events).await
+}
+
+/// Lists the authentication events of a user a page at a time, newest first
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `user_id`: The id of the user
+/// - `page`: The page number, starting at 1
+/// - `per_page`: The number of events per page, at most 100
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns an EventPage, or an AuthError
+#[rocket::get("/users/<user_id>/security-events?<page>&<per_page>")]
+pub async fn list_user_events(_admin: AdminUser, user_id: i64, page: Option<i64>, per_page: Option<i64>, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<Json<EventPage>, AuthError> {
+    let pagination = Pagination::from_query(page, per_page)?;
+    find_user(user_id, users).await?;
+
+    event_page(Some(user_id), pagination, events).await
+}
@@ -0,0 +1,70 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+
+use crate::crypto::csrf::CsrfGuard;
+use crate::db::store::{DynSessionStore, DynUserStore};
+use crate::db::user::AdminUser;
+use crate::errors::auth_error::AuthError;
+use crate::routes::admin::users::find_user;
+use crate::routes::responses::{MessageResponse, SessionInfo};
+
+/// Lists the active refresh sessions of a user, newest first
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `user_id`: The id of the user
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+///
+/// # Returns
+/// Returns the sessions, or an AuthError
+#[rocket::get("/users/<user_id>/sessions")]
+pub async fn list_sessions(_admin: AdminUser, user_id: i64, users: &State<DynUserStore>, sessions: &State<DynSessionStore>) -> Result<Json<Vec<SessionInfo>>, AuthError> {
+    find_user(user_id, users).await?;
+    let active_sessions = sessions.list_refresh_sessions(user_id).await?;
+
+    Ok(Json(active_sessions.into_iter().map(SessionInfo::from).collect()))
+}
+
+/// Forces a user to log out everywhere by revoking all of their refresh sessions. They are signed out once their current JWTs expire
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+///
+/// # Returns
+/// Returns a MessageResponse, or an AuthError
+#[rocket::delete("/users/<user_id>/sessions")]
+pub async fn revoke_sessions(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, users: &State<DynUserStore>, sessions: &State<DynSessionStore>) -> Result<Json<MessageResponse>, AuthError> {
+    find_user(user_id, users).await?;
+    sessions.revoke_user_sessions(user_id).await?;
+
+    Ok(MessageResponse::new("Sessions revoked"))
+}
+
+/// Revokes one refresh session of a user
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `session_id`: The session to revoke, which must be an active session of the user
+/// - `sessions`: The refresh session store managed by rocket
+///
+/// # Returns
+/// Returns a MessageResponse, or an AuthError
+#[rocket::delete("/users/<user_id>/sessions/<session_id>")]
+pub async fn revoke_session(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, session_id: Uuid, sessions: &State<DynSessionStore>) -> Result<Json<MessageResponse>, AuthError> {
+    let active_sessions = sessions.list_refresh_sessions(user_id).await?;
+    if !active_sessions.iter().any(|session| session.session_id == session_id) {
+        return Err(AuthError::SessionNotFound);
+    }
+
+    sessions.revoke_refresh_session(session_id).await?;
+
+    Ok(MessageResponse::new("Session revoked"))
+}
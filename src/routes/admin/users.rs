@@ -0,0 +1,157 @@
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::crypto::create_refresh::random_token;
+use crate::crypto::csrf::CsrfGuard;
+use crate::crypto::hash::PasswordHash;
+use crate::db::auth_events::EventType;
+use crate::db::store::{DynEventStore, DynSessionStore, DynUserStore};
+use crate::db::user::{AdminUser, User};
+use crate::errors::auth_error::AuthError;
+use crate::routes::audit::{record_event, ClientInfo};
+use crate::routes::pagination::Pagination;
+use crate::routes::responses::{MessageResponse, TemporaryPasswordResponse, UserDetails, UserPage, UserSummary};
+
+/// Lists users a page at a time, optionally searching by username
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `search`: Text the username must contain, ignoring case
+/// - `page`: The page number, starting at 1
+/// - `per_page`: The number of users per page, at most 100
+/// - `users`: The user store managed by rocket
+///
+/// # Returns
+/// Returns a UserPage, or an AuthError
+#[rocket::get("/users?<search>&<page>&<per_page>")]
+pub async fn list_users(_admin: AdminUser, search: Option<&str>, page: Option<i64>, per_page: Option<i64>, users: &State<DynUserStore>) -> Result<Json<UserPage>, AuthError> {
+    let pagination = Pagination::from_query(page, per_page)?;
+    let search = search.filter(|search| !search.is_empty());
+
+    let total = users.count_users(search).await?;
+    let matched_users = users.list_users(search, pagination.per_page, pagination.offset()).await?;
+
+    Ok(Json(UserPage {
+        users: matched_users.into_iter().map(UserSummary::from).collect(),
+        page: pagination.page,
+        per_page: pagination.per_page,
+        total
+    }))
+}
+
+/// Shows a user with their roles
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `user_id`: The id of the user
+/// - `users`: The user store managed by rocket
+///
+/// # Returns
+/// Returns the UserDetails, or an AuthError
+#[rocket::get("/users/<user_id>")]
+pub async fn get_user(_admin: AdminUser, user_id: i64, users: &State<DynUserStore>) -> Result<Json<UserDetails>, AuthError> {
+    get_user_details(user_id, users).await
+}
+
+/// Stops a user from signing in and revokes their refresh sessions
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the updated UserDetails, or an AuthError
+#[rocket::post("/users/<user_id>/disable")]
+pub async fn disable_user(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, client: ClientInfo, users: &State<DynUserStore>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<Json<UserDetails>, AuthError> {
+    find_user(user_id, users).await?;
+    users.set_user_disabled(user_id, true).await?;
+    sessions.revoke_user_sessions(user_id).await?;
+    record_event(events, EventType::AccountDisabled, Some(user_id), &client).await;
+
+    get_user_details(user_id, users).await
+}
+
+/// Allows a disabled user to sign in again
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the updated UserDetails, or an AuthError
+#[rocket::post("/users/<user_id>/enable")]
+pub async fn enable_user(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, client: ClientInfo, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<Json<UserDetails>, AuthError> {
+    find_user(user_id, users).await?;
+    users.set_user_disabled(user_id, false).await?;
+    record_event(events, EventType::AccountEnabled, Some(user_id), &client).await;
+
+    get_user_details(user_id, users).await
+}
+
+/// Replaces a user's password with a random temporary one and revokes their refresh sessions, so they must sign in again with it
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns the temporary password, or an AuthError
+#[rocket::post("/users/<user_id>/reset-password")]
+pub async fn reset_password(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, client: ClientInfo, users: &State<DynUserStore>, sessions: &State<DynSessionStore>, events: &State<DynEventStore>) -> Result<Json<TemporaryPasswordResponse>, AuthError> {
+    find_user(user_id, users).await?;
+
+    let temporary_password = random_token()?;
+    let hashed_password = PasswordHash::try_from(temporary_password.as_str())?;
+    users.update_password(user_id, &hashed_password.value()).await?;
+    sessions.revoke_user_sessions(user_id).await?;
+    record_event(events, EventType::PasswordChange, Some(user_id), &client).await;
+
+    Ok(Json(TemporaryPasswordResponse { temporary_password }))
+}
+
+/// Deletes a user and revokes their refresh sessions. The account is removed for good once the deletion grace period has passed
+///
+/// # Arguments
+/// - `_admin`: The admin role guard
+/// - `_csrf`: The CSRF request guard
+/// - `user_id`: The id of the user
+/// - `client`: The client details recorded in the audit log
+/// - `users`: The user store managed by rocket
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns a MessageResponse, or an AuthError
+#[rocket::delete("/users/<user_id>")]
+pub async fn delete_user(_admin: AdminUser, _csrf: CsrfGuard, user_id: i64, client: ClientInfo, users: &State<DynUserStore>, events: &State<DynEventStore>) -> Result<Json<MessageResponse>, AuthError> {
+    if !users.soft_delete_user(user_id).await? {
+        return Err(AuthError::UserNotFound);
+    }
+    record_event(events, EventType::AccountDeleted, Some(user_id), &client).await;
+
+    Ok(MessageResponse::new("User deleted"))
+}
+
+pub(crate) async fn find_user(user_id: i64, users: &State<DynUserStore>) -> Result<User, AuthError> {
+    users.read_user_by_id(user_id).await?.ok_or(AuthError::UserNotFound)
+}
+
+async fn get_user_details(user_id: i64, users: &State<DynUserStore>) -> Result<Json<UserDetails>, AuthError> {
+    let user = find_user(user_id, users).await?;
+    let roles = users.get_roles(user_id).await?;
+
+    Ok(Json(UserDetails { user: UserSummary::from(user), roles }))
+}
@@ -0,0 +1,71 @@
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+
+use crate::db::auth_events::{EventType, NewAuthEvent};
+use crate::db::store::DynEventStore;
+use crate::errors::auth_error::AuthError;
+use crate::routes::pagination::Pagination;
+use crate::routes::responses::EventPage;
+
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// The client details recorded with authentication events. Both are taken from the request as sent, so they are only as trustworthy as the proxy in front of the server
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            ip_address: request.client_ip().map(|ip| ip.to_string()),
+            user_agent: request.headers().get_one("User-Agent")
+                .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect())
+        })
+    }
+}
+
+/// Records an authentication event. A failure is logged rather than returned, so the audit log never stops a user from signing in
+///
+/// # Arguments
+/// - `events`: The event store
+/// - `event_type`: What happened
+/// - `user_id`: The user it happened to, if known
+/// - `client`: The client that made the request
+pub async fn record_event(events: &DynEventStore, event_type: EventType, user_id: Option<i64>, client: &ClientInfo) {
+    let event = NewAuthEvent {
+        user_id,
+        event_type,
+        ip_address: client.ip_address.clone(),
+        user_agent: client.user_agent.clone()
+    };
+
+    if let Err(e) = events.record_event(&event).await {
+        rocket::error!("Failed to record {} event: {}", event_type.as_str(), e);
+    }
+}
+
+/// Loads a page of events for the security event routes
+///
+/// # Arguments
+/// - `user_id`: Only the events of this user, or every event when None
+/// - `pagination`: The page to load
+/// - `events`: The audit log managed by rocket
+///
+/// # Returns
+/// Returns an EventPage, or an AuthError
+pub(crate) async fn event_page(user_id: Option<i64>, pagination: Pagination, events: &State<DynEventStore>) -> Result<Json<EventPage>, AuthError> {
+    let total = events.count_events(user_id).await?;
+    let matched_events = events.list_events(user_id, pagination.per_page, pagination.offset()).await?;
+
+    Ok(Json(EventPage {
+        events: matched_events,
+        page: pagination.page,
+        per_page: pagination.per_page,
+        total
+    }))
+}
@@ -0,0 +1,19 @@
+use rocket::http::Status;
+use rocket::Request;
+
+use crate::routes::responses::{ApiError, INTERNAL_ERROR};
+
+/// Turns errors raised outside of route handlers, such as a failed request guard or malformed JSON, into the same JSON error envelope the routes use
+#[rocket::catch(default)]
+pub fn default_catcher(status: Status, _request: &Request) -> ApiError {
+    match status.code {
+        400 => ApiError::new(status, "bad_request", "The request is malformed"),
+        401 => ApiError::new(status, "unauthorized", "A valid access token is required"),
+        403 => ApiError::new(status, "forbidden", "The request is not allowed"),
+        404 => ApiError::new(status, "not_found", "The requested resource was not found"),
+        415 => ApiError::new(status, "unsupported_media_type", "The request body must be JSON"),
+        422 => ApiError::new(status, "invalid_body", "The request body does not match the expected format"),
+        500 => ApiError::new(status, "internal_error", INTERNAL_ERROR),
+        _ => ApiError::new(status, "error", status.reason().unwrap_or("An error has occured"))
+    }
+}
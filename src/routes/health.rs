@@ -0,0 +1,57 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::tokio::time::timeout;
+use rocket::State;
+
+use crate::crypto::jwt::check_jwt_config;
+use crate::crypto::token_hash::check_refresh_token_key;
+use crate::db::store::DynUserStore;
+use crate::routes::responses::{CheckResult, HealthResponse, ReadinessChecks, ReadinessResponse};
+
+/// How long the database checks may take before the server is reported as not ready, so a probe is not held open while the pool waits for a connection
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Liveness route; answers as long as the process is running and able to serve requests
+///
+/// # Returns
+/// `{"status": "ok"}`
+#[rocket::get("/healthz")]
+pub fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+/// Readiness route; checks that the database can be reached, its migrations match the server, and the JWT and refresh token keys are set
+///
+/// # Arguments
+/// - `users`: The user store managed by rocket, whose database is checked
+///
+/// # Returns
+/// The result of each check, with a 200 status if all passed or 503 otherwise
+#[rocket::get("/readyz")]
+pub async fn readyz(users: &State<DynUserStore>) -> (Status, Json<ReadinessResponse>) {
+    let database = timed_check(users.ping()).await;
+    // The schema cannot be read without the database, so its check repeats the database's failure
+    let migrations = match database.is_ok() {
+        true => timed_check(users.check_schema()).await,
+        false => CheckResult::from(Err::<(), _>("the database cannot be reached"))
+    };
+    let signing_keys = CheckResult::from(check_jwt_config().and_then(|_| check_refresh_token_key()));
+
+    let checks = ReadinessChecks { database, migrations, signing_keys };
+    if checks.database.is_ok() && checks.migrations.is_ok() && checks.signing_keys.is_ok() {
+        return (Status::Ok, Json(ReadinessResponse { status: "ready", checks }));
+    }
+
+    rocket::warn!("Server is not ready: {:?}", checks);
+    (Status::ServiceUnavailable, Json(ReadinessResponse { status: "not_ready", checks }))
+}
+
+async fn timed_check<E: std::fmt::Display>(check: impl Future<Output = Result<(), E>>) -> CheckResult {
+    match timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => CheckResult::from(result),
+        Err(_) => CheckResult::from(Err::<(), _>("timed out"))
+    }
+}
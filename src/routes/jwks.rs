@@ -0,0 +1,13 @@
+use jsonwebtoken::jwk::JwkSet;
+use rocket::serde::json::Json;
+
+use crate::crypto::jwt::jwks;
+
+/// JWKS route, publishing the public key JWTs are signed with so other services can verify them, for example with the auth-guard crate. The key set is empty when JWTs are signed with the HS256 secret
+///
+/// # Returns
+/// The key set in the RFC 7517 format
+#[rocket::get("/.well-known/jwks.json")]
+pub fn publish_jwks() -> Json<JwkSet> {
+    Json(jwks())
+}
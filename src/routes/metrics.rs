@@ -0,0 +1,25 @@
+use rocket::http::{ContentType, Status};
+use rocket::State;
+
+use crate::db::store::DynUserStore;
+use crate::metrics::collectors::METRICS;
+
+/// Metrics route for Prometheus, returning every metric in the text exposition format. The database pool gauges are read when the route is scraped
+///
+/// # Arguments
+/// - `users`: The user store managed by rocket, whose pool is reported
+///
+/// # Returns
+/// The metrics, or a 500 status if they could not be encoded
+#[rocket::get("/metrics")]
+pub fn export_metrics(users: &State<DynUserStore>) -> Result<(ContentType, String), Status> {
+    METRICS.set_pool_status(users.pool_status());
+
+    match METRICS.encode() {
+        Ok(body) => Ok((ContentType::new("text", "plain").with_params(("version", "0.0.4")), body)),
+        Err(e) => {
+            rocket::error!("Failed to encode metrics: {}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
@@ -1 +1,10 @@
-pub mod accounts;
\ No newline at end of file
+pub mod accounts;
+pub mod admin;
+pub mod tokens;
+pub mod responses;
+pub mod catchers;
+pub mod audit;
+pub mod pagination;
+pub mod metrics;
+pub mod health;
+pub mod jwks;
\ No newline at end of file
@@ -0,0 +1,37 @@
+use crate::errors::auth_error::AuthError;
+
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+
+/// The page and per_page query parameters of the listing routes
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+    offset: i64
+}
+
+impl Pagination {
+    /// Checks the query parameters, defaulting to the first page of 20
+    ///
+    /// # Arguments
+    /// - `page`: The page number, starting at 1
+    /// - `per_page`: The number of items per page, at most 100
+    ///
+    /// # Returns
+    /// The Pagination, or AuthError::Validation if either is out of range or the page is too far in for its offset to fit in an i64
+    pub fn from_query(page: Option<i64>, per_page: Option<i64>) -> Result<Self, AuthError> {
+        let page = page.unwrap_or(1);
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page < 1 || !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(AuthError::Validation(format!("page must be at least 1 and per_page between 1 and {}", MAX_PER_PAGE)));
+        }
+        let offset = (page - 1).checked_mul(per_page).ok_or_else(|| AuthError::Validation("page is too large".to_string()))?;
+
+        Ok(Self { page, per_page, offset })
+    }
+
+    /// The number of items before this page
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+}
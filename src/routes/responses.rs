@@ -0,0 +1,373 @@
+use chrono::{DateTime, Utc};
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::{Json, serde_json};
+use rocket::serde::Serialize;
+use std::io::Cursor;
+use uuid::Uuid;
+
+use crate::crypto::jwt::jwt_expire_time;
+use crate::db::auth_events::AuthEvent;
+use crate::db::create_refresh_entry::RefreshToken;
+use crate::db::user::User;
+
+pub const INTERNAL_ERROR: &str = "An internal server error has occured";
+
+/// The public details of a user returned by the account routes
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct UserInfo {
+    pub user_id: i64,
+    pub username: String
+}
+
+/// Returned by sign-in and refresh. The access token is left out in browser session mode, where it is only sent as a cookie
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct TokenResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub refresh_expires_in: i64,
+    pub user: UserInfo
+}
+
+impl TokenResponse {
+    /// # Arguments
+    /// - `access_token`: The JWT, or None when it is delivered as a cookie
+    /// - `refresh_expires_in`: The number of seconds until the refresh session expires
+    /// - `user`: The user the tokens were issued to
+    pub fn new(access_token: Option<String>, refresh_expires_in: i64, user: UserInfo) -> Json<Self> {
+        Json(Self {
+            access_token,
+            token_type: "Bearer",
+            expires_in: jwt_expire_time(),
+            refresh_expires_in,
+            user
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct MessageResponse {
+    pub message: String
+}
+
+impl MessageResponse {
+    pub fn new(message: &str) -> Json<Self> {
+        Json(Self { message: message.to_string() })
+    }
+}
+
+/// The signed in user's own profile
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ProfileResponse {
+    pub user_id: i64,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: bool
+}
+
+impl From<User> for ProfileResponse {
+    fn from(user: User) -> Self {
+        Self {
+            user_id: user.user_id,
+            username: user.username,
+            display_name: user.display_name,
+            email: user.email,
+            email_verified: user.email_verified_at.is_some()
+        }
+    }
+}
+
+/// A user as shown to administrators
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct UserSummary {
+    pub user_id: i64,
+    pub username: String,
+    pub disabled_at: Option<DateTime<Utc>>
+}
+
+impl From<User> for UserSummary {
+    fn from(user: User) -> Self {
+        Self { user_id: user.user_id, username: user.username, disabled_at: user.disabled_at }
+    }
+}
+
+/// A single user with their roles, returned by the admin routes
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct UserDetails {
+    #[serde(flatten)]
+    pub user: UserSummary,
+    pub roles: Vec<String>
+}
+
+/// A page of users; total is the number of users matching the search across every page
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct UserPage {
+    pub users: Vec<UserSummary>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64
+}
+
+/// A page of authentication events, newest first; total is the number of events across every page
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct EventPage {
+    pub events: Vec<AuthEvent>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64
+}
+
+/// An active refresh session. last_refreshed_at is when its current refresh token was issued
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SessionInfo {
+    pub session_id: Uuid,
+    pub last_refreshed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>
+}
+
+impl From<RefreshToken> for SessionInfo {
+    fn from(token: RefreshToken) -> Self {
+        Self { session_id: token.session_id, last_refreshed_at: token.created_at, expires_at: token.expires_at }
+    }
+}
+
+/// Returned when an administrator resets a password, to be passed on to the user to sign in with
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct TemporaryPasswordResponse {
+    pub temporary_password: String
+}
+
+/// Everything stored about a user, returned by GET /me/export. Password and token hashes are left out, as they cannot be read back into anything useful
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AccountExport {
+    pub exported_at: DateTime<Utc>,
+    pub account: AccountData,
+    pub roles: Vec<String>,
+    pub refresh_tokens: Vec<RefreshTokenData>,
+    pub security_events: Vec<AuthEvent>
+}
+
+/// The stored fields of a user account in an AccountExport
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AccountData {
+    pub user_id: i64,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>
+}
+
+impl From<User> for AccountData {
+    fn from(user: User) -> Self {
+        Self {
+            user_id: user.user_id,
+            username: user.username,
+            display_name: user.display_name,
+            email: user.email,
+            email_verified_at: user.email_verified_at,
+            disabled_at: user.disabled_at
+        }
+    }
+}
+
+/// A stored refresh token in an AccountExport, including rotated and revoked ones
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct RefreshTokenData {
+    pub session_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>
+}
+
+impl From<RefreshToken> for RefreshTokenData {
+    fn from(token: RefreshToken) -> Self {
+        Self {
+            session_id: token.session_id,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            rotated_at: token.rotated_at,
+            revoked_at: token.revoked_at
+        }
+    }
+}
+
+/// An AccountExport sent as a file download
+#[derive(rocket::Responder, Debug)]
+pub struct ExportDownload {
+    pub inner: Json<AccountExport>,
+    pub disposition: Header<'static>
+}
+
+impl From<AccountExport> for ExportDownload {
+    fn from(export: AccountExport) -> Self {
+        let filename = format!("account-{}.json", export.account.user_id);
+        Self {
+            inner: Json(export),
+            disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+        }
+    }
+}
+
+/// The body of /healthz
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct HealthResponse {
+    pub status: &'static str
+}
+
+/// The body of /readyz; status is ready when every check passed and not_ready otherwise
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+    pub checks: ReadinessChecks
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ReadinessChecks {
+    pub database: CheckResult,
+    pub migrations: CheckResult,
+    pub signing_keys: CheckResult
+}
+
+/// The result of one readiness check. The reason it failed is only logged, as database and schema errors can name hosts, users and tables
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct CheckResult {
+    pub status: &'static str,
+    #[serde(skip_serializing)]
+    pub error: Option<String>
+}
+
+impl CheckResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl<E: std::fmt::Display> From<Result<(), E>> for CheckResult {
+    fn from(result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => Self { status: "ok", error: None },
+            Err(e) => Self { status: "error", error: Some(e.to_string()) }
+        }
+    }
+}
+
+/// The body of /introspect, following RFC 7662. Only `active` is returned for a token that is not active, so nothing is revealed about it
+#[derive(Serialize, Debug, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    /// The user_id of the token's owner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// The user's roles, separated by spaces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// When the token expires, in seconds since the Unix epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    /// access_token or refresh_token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<&'static str>
+}
+
+impl IntrospectionResponse {
+    pub fn inactive() -> Self {
+        Self::default()
+    }
+
+    /// # Arguments
+    /// - `user`: The account the token belongs to
+    /// - `roles`: The account's roles
+    /// - `exp`: When the token expires, in seconds since the Unix epoch
+    /// - `token_type`: access_token or refresh_token
+    pub fn active(user: User, roles: Vec<String>, exp: i64, token_type: &'static str) -> Self {
+        Self {
+            active: true,
+            sub: Some(user.user_id.to_string()),
+            username: Some(user.username),
+            scope: Some(roles.join(" ")),
+            exp: Some(exp),
+            token_type: Some(token_type)
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct ErrorBody<'a> {
+    error: ErrorDetail<'a>
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct ErrorDetail<'a> {
+    code: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<&'a str>
+}
+
+/// An error returned to the client as `{"error": {"code": ..., "message": ..., "correlation_id": ...}}`. The code is a stable machine-readable identifier and the message is meant for people. Routes return an AuthError which is turned into this
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub code: &'static str,
+    pub message: String,
+    pub correlation_id: Option<String>
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &'static str, message: &str) -> Self {
+        Self { status, code, message: message.to_string(), correlation_id: None }
+    }
+
+    pub fn with_correlation_id(mut self, correlation_id: &str) -> Self {
+        self.correlation_id = Some(correlation_id.to_string());
+        self
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let body = serde_json::to_string(&ErrorBody {
+            error: ErrorDetail { code: self.code, message: &self.message, correlation_id: self.correlation_id.as_deref() }
+        }).map_err(|_| Status::InternalServerError)?;
+
+        let mut response = Response::build();
+        response
+            .status(self.status)
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body));
+
+        if let Some(correlation_id) = self.correlation_id {
+            response.raw_header("X-Correlation-Id", correlation_id);
+        }
+
+        response.ok()
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::Utc;
+use rocket::form::{Form, FromForm};
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::crypto::client_auth::ApiClient;
+use crate::crypto::jwt::{JwtStatus, decode_jwt};
+use crate::crypto::token_hash::{hash_refresh_token, verify_refresh_token};
+use crate::db::store::{DynSessionStore, DynUserStore};
+use crate::db::user::User;
+use crate::errors::auth_error::AuthError;
+use crate::routes::responses::IntrospectionResponse;
+
+/// The form body of /introspect and /revoke. The hint is either access_token or refresh_token, and only changes which kind of token is looked for first
+#[derive(FromForm, Debug)]
+pub struct TokenRequest<'r> {
+    pub token: &'r str,
+    pub token_type_hint: Option<&'r str>
+}
+
+impl TokenRequest<'_> {
+    /// Whether the token should be looked up as a refresh token before trying it as a JWT
+    pub fn refresh_first(&self) -> bool {
+        self.token_type_hint == Some("refresh_token")
+    }
+}
+
+/// Token introspection route (RFC 7662) for services that cannot verify tokens themselves. The caller authenticates as a client from OAUTH_CLIENTS with HTTP Basic and sends the token as a form field. Access tokens are active while they are unexpired, have not been revoked through /revoke and their session has not been revoked, and refresh tokens while they are the current, unrevoked token of their session; in both cases the account must still exist and not be disabled
+///
+/// # Arguments
+/// - `_client`: The client making the request
+/// - `request`: The token and an optional token_type_hint
+/// - `users`: The user store managed by rocket
+/// - `sessions`: The refresh session store managed by rocket
+///
+/// # Returns
+/// The token's owner, roles as the scope and expiry if it is active, `{"active": false}` if it is not, or an AuthError
+#[rocket::post("/introspect", data = "<request>")]
+pub async fn introspect(_client: ApiClient, request: Form<TokenRequest<'_>>, users: &State<DynUserStore>, sessions: &State<DynSessionStore>) -> Result<Json<IntrospectionResponse>, AuthError> {
+    let response = match request.refresh_first() {
+        true => match introspect_refresh_token(request.token, users, sessions).await? {
+            Some(response) => Some(response),
+            None => introspect_access_token(request.token, users, sessions).await?
+        },
+        false => match introspect_access_token(request.token, users, sessions).await? {
+            Some(response) => Some(response),
+            None => introspect_refresh_token(request.token, users, sessions).await?
+        }
+    };
+
+    Ok(Json(response.unwrap_or_else(IntrospectionResponse::inactive)))
+}
+
+/// Introspects a JWT issued by create_jwt
+///
+/// # Returns
+/// The response for an active token, or None if the token is not an active JWT
+async fn introspect_access_token(token: &str, users: &DynUserStore, sessions: &DynSessionStore) -> Result<Option<IntrospectionResponse>, AuthError> {
+    let Ok(JwtStatus::Valid(claims)) = decode_jwt(token) else {
+        return Ok(None);
+    };
+    if let Some(jti) = claims.jti && sessions.is_access_token_revoked(jti).await? {
+        return Ok(None);
+    }
+    let Some(account) = active_account(claims.user_id, users).await? else {
+        return Ok(None);
+    };
+
+    // Tokens issued before sessions were tracked have no session to check
+    if let Some(session_id) = claims.session_id {
+        let active_sessions = sessions.list_refresh_sessions(claims.user_id).await?;
+        if !active_sessions.iter().any(|session| session.session_id == session_id) {
+            return Ok(None);
+        }
+    }
+
+    let roles = users.get_roles(account.user_id).await?;
+    Ok(Some(IntrospectionResponse::active(account, roles, claims.exp, "access_token")))
+}
+
+/// Introspects a refresh token by looking up its hash in the session store
+///
+/// # Returns
+/// The response for an active token, or None if the token is not an active refresh token
+async fn introspect_refresh_token(token: &str, users: &DynUserStore, sessions: &DynSessionStore) -> Result<Option<IntrospectionResponse>, AuthError> {
+    let Some(entry) = sessions.get_refresh_entry(&hash_refresh_token(token)).await? else {
+        return Ok(None);
+    };
+    if !verify_refresh_token(token, &entry.token_hash) || entry.rotated_at.is_some()
+        || entry.revoked_at.is_some() || entry.expires_at <= Utc::now() {
+        return Ok(None);
+    }
+    let Some(account) = active_account(entry.user_id, users).await? else {
+        return Ok(None);
+    };
+
+    let roles = users.get_roles(account.user_id).await?;
+    Ok(Some(IntrospectionResponse::active(account, roles, entry.expires_at.timestamp(), "refresh_token")))
+}
+
+/// Loads the account a token belongs to, unless it has been deleted or disabled
+async fn active_account(user_id: i64, users: &DynUserStore) -> Result<Option<User>, AuthError> {
+    Ok(users.read_user_by_id(user_id).await?.filter(|account| account.disabled_at.is_none()))
+}
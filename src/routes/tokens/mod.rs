@@ -0,0 +1,2 @@
+pub mod introspect;
+pub mod revoke;
\ No newline at end of file
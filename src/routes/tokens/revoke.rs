@@ -0,0 +1,65 @@
+use chrono::TimeZone;
+use rocket::form::Form;
+use rocket::State;
+
+use crate::crypto::client_auth::RevokingClient;
+use crate::crypto::jwt::{JwtStatus, decode_jwt};
+use crate::crypto::token_hash::{hash_refresh_token, verify_refresh_token};
+use crate::db::store::DynSessionStore;
+use crate::errors::auth_error::AuthError;
+use crate::routes::tokens::introspect::TokenRequest;
+
+/// Token revocation route (RFC 7009), letting a client release tokens it no longer needs. The caller authenticates as a client from OAUTH_CLIENTS with HTTP Basic, must be listed in OAUTH_REVOKE_CLIENTS as it can revoke any user's tokens, and sends the token as a form field. A refresh token revokes its whole session, and an access token has its jti added to the denylist so it is refused until it expires. Unknown, expired and already revoked tokens are accepted too, as the RFC asks, so the response says nothing about the token
+///
+/// # Arguments
+/// - `client`: The client making the request
+/// - `request`: The token and an optional token_type_hint
+/// - `sessions`: The refresh session store managed by rocket
+///
+/// # Returns
+/// An empty 200 response, or an AuthError if the store failed
+#[rocket::post("/revoke", data = "<request>")]
+pub async fn revoke(client: RevokingClient, request: Form<TokenRequest<'_>>, sessions: &State<DynSessionStore>) -> Result<(), AuthError> {
+    let revoked = match request.refresh_first() {
+        true => revoke_refresh_token(request.token, sessions).await? || revoke_access_token(request.token, sessions).await?,
+        false => revoke_access_token(request.token, sessions).await? || revoke_refresh_token(request.token, sessions).await?
+    };
+
+    if revoked {
+        rocket::info!("Token revoked by client {}", client.client_id);
+    }
+    Ok(())
+}
+
+/// Adds an unexpired JWT to the denylist
+///
+/// # Returns
+/// True if the token was a JWT that could be revoked
+async fn revoke_access_token(token: &str, sessions: &DynSessionStore) -> Result<bool, AuthError> {
+    let Ok(JwtStatus::Valid(claims)) = decode_jwt(token) else {
+        return Ok(false);
+    };
+    // Tokens issued before revocation was supported have no jti and stay valid until they expire
+    let (Some(jti), Some(expires_at)) = (claims.jti, chrono::Utc.timestamp_opt(claims.exp, 0).single()) else {
+        return Ok(false);
+    };
+
+    sessions.revoke_access_token(jti, expires_at).await?;
+    Ok(true)
+}
+
+/// Revokes the session a refresh token belongs to
+///
+/// # Returns
+/// True if the token was a known refresh token
+async fn revoke_refresh_token(token: &str, sessions: &DynSessionStore) -> Result<bool, AuthError> {
+    let Some(entry) = sessions.get_refresh_entry(&hash_refresh_token(token)).await? else {
+        return Ok(false);
+    };
+    if !verify_refresh_token(token, &entry.token_hash) {
+        return Ok(false);
+    }
+
+    sessions.revoke_refresh_session(entry.session_id).await?;
+    Ok(true)
+}
@@ -0,0 +1,2 @@
+pub mod otel;
+pub mod request_span;
\ No newline at end of file
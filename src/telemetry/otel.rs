@@ -0,0 +1,103 @@
+use std::env;
+
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::{global, Context};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use rocket::http::HeaderMap;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::config::env_vars::env_or;
+
+/// Errors from setting up tracing
+#[derive(Debug)]
+pub enum TracingError {
+    Exporter(opentelemetry_otlp::ExporterBuildError),
+    Subscriber(tracing::subscriber::SetGlobalDefaultError)
+}
+
+impl std::fmt::Display for TracingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exporter(e) => write!(f, "failed to create the OTLP exporter: {}", e),
+            Self::Subscriber(e) => write!(f, "failed to install the tracing subscriber: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for TracingError {}
+
+/// Sends spans to an OpenTelemetry collector over OTLP/HTTP when OTEL_EXPORTER_OTLP_ENDPOINT (or OTEL_EXPORTER_OTLP_TRACES_ENDPOINT) is set. The other standard OTEL_ variables, such as OTEL_TRACES_SAMPLER, are read by the exporter and SDK
+///
+/// # Returns
+/// The tracer provider, to be shut down when the server stops so the last spans are sent, or None if tracing is not configured
+pub fn setup_tracing() -> Result<Option<SdkTracerProvider>, TracingError> {
+    let configured = ["OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"].iter()
+        .any(|name| env::var(name).is_ok_and(|endpoint| !endpoint.is_empty()));
+    if !configured {
+        return Ok(None);
+    }
+
+    // The exporter reads the endpoint, headers and timeout from the OTEL_EXPORTER_OTLP_ variables
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .map_err(TracingError::Exporter)?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(env_or("OTEL_SERVICE_NAME", "auth-server".to_string())).build())
+        .build();
+
+    install_tracing(&provider)?;
+    Ok(Some(provider))
+}
+
+/// Sends the spans of every request to a tracer provider and reads W3C trace context from incoming requests. Used by setup_tracing, and by the tests with an in-memory exporter
+///
+/// # Arguments
+/// - `provider`: The tracer provider spans are sent to
+///
+/// # Returns
+/// Nothing, or a TracingError if a tracing subscriber has already been installed
+pub fn install_tracing(provider: &SdkTracerProvider) -> Result<(), TracingError> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("server"));
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::set_global_default(subscriber).map_err(TracingError::Subscriber)
+}
+
+/// Reads the trace a request belongs to from its traceparent and tracestate headers
+///
+/// # Arguments
+/// - `headers`: The headers of the request
+///
+/// # Returns
+/// The remote trace context, or an empty context if the request has none
+pub fn extract_context(headers: &HeaderMap<'_>) -> Context {
+    let extractor = HeaderExtractor {
+        headers,
+        names: headers.iter().map(|header| header.name().as_str().to_string()).collect()
+    };
+    global::get_text_map_propagator(|propagator| propagator.extract(&extractor))
+}
+
+/// Rocket's headers as seen by the propagator. Header names are copied out, as HeaderMap only lends them for as long as a single header
+struct HeaderExtractor<'a, 'h> {
+    headers: &'a HeaderMap<'h>,
+    names: Vec<String>
+}
+
+impl Extractor for HeaderExtractor<'_, '_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.headers.get_one(key)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.names.iter().map(String::as_str).collect()
+    }
+}
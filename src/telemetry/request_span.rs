@@ -0,0 +1,56 @@
+use rocket::data::Data;
+use rocket::outcome::Outcome;
+use rocket::route::{self, Handler, Route};
+use rocket::Request;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::logs::request_id::RequestId;
+use crate::telemetry::otel::extract_context;
+
+/// Runs each route's handler, including its request guards, inside a span for the request. The span joins the caller's trace when the request carries a W3C traceparent header
+///
+/// # Arguments
+/// - `routes`: The routes to mount
+///
+/// # Returns
+/// The same routes with their handlers wrapped
+pub fn with_tracing(routes: Vec<Route>) -> Vec<Route> {
+    routes.into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(TracedHandler(route.handler));
+            route
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct TracedHandler(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for TracedHandler {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let route = request.route().map(|route| route.uri.path()).unwrap_or_default();
+        let span = tracing::info_span!(
+            "request",
+            otel.name = %format_args!("{} {}", request.method(), route),
+            otel.kind = "server",
+            http.request.method = %request.method(),
+            http.route = route,
+            url.path = %request.uri().path(),
+            request_id = RequestId::of(request),
+            http.response.status_code = tracing::field::Empty
+        );
+        // Fails only when no tracing subscriber is installed, in which case the span is not recorded anyway
+        let _ = span.set_parent(extract_context(request.headers()));
+
+        let outcome = self.0.handle(request, data).instrument(span.clone()).await;
+        let status = match &outcome {
+            Outcome::Success(response) => response.status(),
+            Outcome::Error(status) | Outcome::Forward((_, status)) => *status
+        };
+        span.record("http.response.status_code", status.code);
+
+        outcome
+    }
+}
@@ -0,0 +1,234 @@
+mod common;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use serde_json::Value;
+use server::cli::admin;
+use server::db::store::Stores;
+use server::db::user::ADMIN_ROLE;
+
+use common::{bearer, client_with_stores, create_account, credentials, refresh, signin, stores, unique_username, PASSWORD};
+
+/// Creates a new user with the admin role, returning their username
+async fn create_admin(client: &Client, stores: &Stores) -> String {
+    let username = unique_username();
+    create_account(client, &username).await;
+    admin::add_role(stores, &username, ADMIN_ROLE).await.unwrap();
+
+    username
+}
+
+/// Signs the admin in again before each request, as the JWTs in the tests expire after a few seconds
+async fn admin_auth(client: &Client, admin: &str) -> Header<'static> {
+    bearer(&signin(client, admin).await.token)
+}
+
+async fn user_id(stores: &Stores, username: &str) -> i64 {
+    admin::find_user(stores, username).await.unwrap().user_id
+}
+
+async fn json(response: rocket::local::asynchronous::LocalResponse<'_>) -> Value {
+    serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+}
+
+#[rocket::async_test]
+async fn admin_routes_require_admin_role() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.get("/admin/users").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client.get("/admin/users").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(json(response).await["error"]["code"], "forbidden");
+}
+
+#[rocket::async_test]
+async fn users_are_searched_and_paginated() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+
+    let prefix = unique_username();
+    for n in 0..3 {
+        create_account(&client, &format!("{}-{}", prefix, n)).await;
+    }
+
+    let response = client.get(format!("/admin/users?search={}&per_page=2", prefix.to_uppercase()))
+        .header(admin_auth(&client, &admin).await)
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json(response).await;
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["users"].as_array().unwrap().len(), 2);
+    assert_eq!(body["users"][0]["username"], format!("{}-0", prefix));
+
+    let response = client.get(format!("/admin/users?search={}&per_page=2&page=2", prefix))
+        .header(admin_auth(&client, &admin).await)
+        .dispatch().await;
+    let body = json(response).await;
+    assert_eq!(body["users"].as_array().unwrap().len(), 1);
+    assert_eq!(body["users"][0]["username"], format!("{}-2", prefix));
+
+    let response = client.get("/admin/users?per_page=1000").header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    // A page whose offset would overflow is refused rather than reaching the database
+    let response = client.get(format!("/admin/users?per_page=100&page={}", i64::MAX)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[rocket::async_test]
+async fn disabled_user_is_shown_and_can_be_enabled() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    let user_id = user_id(&stores, &username).await;
+
+    let response = client.post(format!("/admin/users/{}/disable", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(!json(response).await["disabled_at"].is_null());
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client.post(format!("/admin/users/{}/enable", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    let body = json(response).await;
+    assert!(body["disabled_at"].is_null());
+    assert_eq!(body["username"], username);
+    signin(&client, &username).await;
+}
+
+#[rocket::async_test]
+async fn sessions_can_be_listed_and_revoked() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let first = signin(&client, &username).await;
+    let second = signin(&client, &username).await;
+    let user_id = user_id(&stores, &username).await;
+
+    let response = client.get(format!("/admin/users/{}/sessions", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    let sessions = json(response).await;
+    assert_eq!(sessions.as_array().unwrap().len(), 2);
+
+    // Sessions are listed newest first, so this is the first sign-in
+    let session_id = sessions[1]["session_id"].as_str().unwrap();
+    let response = client.delete(format!("/admin/users/{}/sessions/{}", user_id, session_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = client.delete(format!("/admin/users/{}/sessions/{}", user_id, session_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let (status, _) = refresh(&client, &first.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+    let (status, second) = refresh(&client, &second.token, &second.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+
+    let response = client.delete(format!("/admin/users/{}/sessions", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let second = second.unwrap();
+    let (status, _) = refresh(&client, &second.token, &second.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn reset_password_returns_a_temporary_password() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let user_id = user_id(&stores, &username).await;
+
+    let response = client.post(format!("/admin/users/{}/reset-password", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let temporary_password = json(response).await["temporary_password"].as_str().unwrap().to_string();
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, &temporary_password))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn deleted_user_is_not_found() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let user_id = user_id(&stores, &username).await;
+
+    let response = client.get(format!("/admin/users/{}", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(json(response).await["roles"], serde_json::json!([]));
+
+    let response = client.delete(format!("/admin/users/{}", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get(format!("/admin/users/{}", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(json(response).await["error"]["code"], "user_not_found");
+    let response = client.delete(format!("/admin/users/{}", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn security_events_are_listed_for_admins() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let admin = create_admin(&client, &stores).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let user_id = user_id(&stores, &username).await;
+
+    client.post(format!("/admin/users/{}/disable", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client.get(format!("/admin/users/{}/security-events", user_id)).header(admin_auth(&client, &admin).await).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json(response).await;
+    let event_types: Vec<&str> = body["events"].as_array().unwrap().iter().map(|event| event["event_type"].as_str().unwrap()).collect();
+    assert_eq!(event_types, vec!["signin_failure", "account_disabled", "signup"]);
+
+    // Failed sign-ins to accounts that do not exist are only in the full list
+    let user_agent = unique_username();
+    client.post("/signin")
+        .header(ContentType::JSON)
+        .header(Header::new("User-Agent", user_agent.clone()))
+        .body(credentials(&unique_username(), PASSWORD))
+        .dispatch().await;
+    let response = client.get("/admin/security-events?per_page=100").header(admin_auth(&client, &admin).await).dispatch().await;
+    let body = json(response).await;
+    let event = body["events"].as_array().unwrap().iter().find(|event| event["user_agent"] == user_agent.as_str()).expect("unknown account event");
+    assert_eq!(event["event_type"], "signin_failure");
+    assert!(event["user_id"].is_null());
+
+    let other = unique_username();
+    create_account(&client, &other).await;
+    let response = client.get("/admin/security-events").header(bearer(&signin(&client, &other).await.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+}
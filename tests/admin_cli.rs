@@ -0,0 +1,142 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use server::cli::admin;
+
+use common::{client_with_stores, create_account, credentials, refresh, signin, stores, test_route_status, unique_username, PASSWORD};
+
+#[rocket::async_test]
+async fn disabled_user_cannot_sign_in_or_refresh() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    admin::set_disabled(&stores, &username, true).await.unwrap();
+
+    // The JWT issued before the account was disabled is refused straight away
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Forbidden);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    assert!(response.into_string().await.unwrap().contains("account_disabled"));
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    admin::set_disabled(&stores, &username, false).await.unwrap();
+    signin(&client, &username).await;
+}
+
+#[rocket::async_test]
+async fn reset_password_revokes_sessions() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    assert_eq!(admin::list_sessions(&stores, &username).await.unwrap().len(), 1);
+
+    admin::reset_password(&stores, &username, "a new password").await.unwrap();
+
+    assert!(admin::list_sessions(&stores, &username).await.unwrap().is_empty());
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, "a new password"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn revoking_one_session_keeps_the_others() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let first = signin(&client, &username).await;
+    let second = signin(&client, &username).await;
+
+    let sessions = admin::list_sessions(&stores, &username).await.unwrap();
+    assert_eq!(sessions.len(), 2);
+
+    // Sessions are listed newest first
+    admin::revoke_session(&stores, sessions[1].session_id).await.unwrap();
+
+    let (status, _) = refresh(&client, &first.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = refresh(&client, &second.token, &second.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn rotating_a_key_keeps_sessions_until_the_server_uses_it() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let key = admin::rotate_key(false).unwrap();
+    assert_eq!(key.len(), 64);
+    assert_ne!(key, admin::rotate_key(false).unwrap());
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn roles_can_be_added_and_removed() {
+    let stores = stores().await;
+    let username = unique_username();
+    admin::create_user(&stores, &username, PASSWORD).await.unwrap();
+
+    admin::add_role(&stores, &username, "support").await.unwrap();
+    admin::add_role(&stores, &username, "admin").await.unwrap();
+    admin::add_role(&stores, &username, "admin").await.unwrap();
+    assert_eq!(admin::list_roles(&stores, &username).await.unwrap(), vec!["admin", "support"]);
+
+    admin::remove_role(&stores, &username, "support").await.unwrap();
+    assert_eq!(admin::list_roles(&stores, &username).await.unwrap(), vec!["admin"]);
+}
+
+#[rocket::async_test]
+async fn deleted_user_is_gone() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    signin(&client, &username).await;
+    admin::add_role(&stores, &username, "admin").await.unwrap();
+
+    admin::delete_user(&stores, &username).await.unwrap();
+
+    assert!(matches!(admin::find_user(&stores, &username).await, Err(admin::AdminError::UserNotFound(_))));
+    assert!(matches!(admin::create_user(&stores, &username, "").await, Err(admin::AdminError::EmptyPassword)));
+    admin::create_user(&stores, &username, PASSWORD).await.unwrap();
+    assert!(matches!(admin::create_user(&stores, &username, PASSWORD).await, Err(admin::AdminError::UserExists(_))));
+}
+
+#[rocket::async_test]
+async fn ids_of_deleted_users_are_not_reused() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    let deleted_id = admin::find_user(&stores, &username).await.unwrap().user_id;
+
+    // The deleted user was the newest, so an id taken from the last row would be handed out again
+    admin::delete_user(&stores, &username).await.unwrap();
+    let new_username = unique_username();
+    create_account(&client, &new_username).await;
+
+    assert!(admin::find_user(&stores, &new_username).await.unwrap().user_id > deleted_id);
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Unauthorized);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use auth_guard::axum_guard::AuthLayer;
+use auth_guard::rocket_guard::{AdminUser, AuthUser};
+use auth_guard::verify::Verifier;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use server::cli::admin;
+use server::db::user::ADMIN_ROLE;
+
+use tower::ServiceExt;
+
+use common::{bearer, client_with_stores, create_account, signin, stores, unique_username};
+
+#[rocket::get("/whoami")]
+fn whoami(user: AuthUser) -> String {
+    format!("{} {}", user.user_id, user.username)
+}
+
+#[rocket::get("/reports")]
+fn reports(admin: AdminUser) -> String {
+    format!("Reports for {}", admin.user.username)
+}
+
+/// Another service that accepts the server's JWTs, verifying them with the shared HS256 secret
+async fn downstream_service() -> Client {
+    let verifier = Verifier::from_secret(b"integration-test-jwt-key");
+    let rocket = rocket::build()
+        .manage(verifier)
+        .mount("/", rocket::routes![whoami, reports]);
+
+    Client::untracked(rocket).await.expect("valid rocket instance")
+}
+
+#[rocket::async_test]
+async fn downstream_services_accept_server_tokens() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let service = downstream_service().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = service.get("/whoami").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().await.unwrap().ends_with(&username));
+
+    assert_eq!(service.get("/whoami").dispatch().await.status(), Status::Unauthorized);
+    assert_eq!(service.get("/whoami").header(bearer("not.a.token")).dispatch().await.status(), Status::Unauthorized);
+
+    // Roles are sent in the scope claim, so a new token is needed once one is granted
+    assert_eq!(service.get("/reports").header(bearer(&session.token)).dispatch().await.status(), Status::Forbidden);
+    admin::add_role(&stores, &username, ADMIN_ROLE).await.unwrap();
+    let session = signin(&client, &username).await;
+    let response = service.get("/reports").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.unwrap(), format!("Reports for {}", username));
+}
+
+/// The same service written with Axum
+fn axum_service() -> axum::Router {
+    async fn whoami(user: AuthUser) -> String {
+        format!("{} {}", user.user_id, user.username)
+    }
+
+    async fn reports(admin: AdminUser) -> String {
+        format!("Reports for {}", admin.user.username)
+    }
+
+    axum::Router::new()
+        .route("/whoami", axum::routing::get(whoami))
+        .route("/reports", axum::routing::get(reports))
+        .layer(AuthLayer::new(Verifier::from_secret(b"integration-test-jwt-key")))
+}
+
+async fn axum_status(uri: &str, token: &str) -> StatusCode {
+    let request = Request::get(uri).header("Authorization", format!("Bearer {}", token)).body(Body::empty()).unwrap();
+
+    axum_service().oneshot(request).await.unwrap().status()
+}
+
+#[rocket::async_test]
+async fn axum_services_accept_server_tokens() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    assert_eq!(axum_status("/whoami", &session.token).await, StatusCode::OK);
+    assert_eq!(axum_status("/whoami", "not.a.token").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(axum_status("/reports", &session.token).await, StatusCode::FORBIDDEN);
+
+    admin::add_role(&stores, &username, ADMIN_ROLE).await.unwrap();
+    let session = signin(&client, &username).await;
+    assert_eq!(axum_status("/reports", &session.token).await, StatusCode::OK);
+}
+
+#[rocket::async_test]
+async fn no_keys_are_published_for_hs256() {
+    let client = client_with_stores(stores().await).await;
+
+    let response = client.get("/.well-known/jwks.json").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.unwrap(), r#"{"keys":[]}"#);
+}
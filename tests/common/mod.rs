@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use std::env;
+use std::sync::Once;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use dotenv::dotenv;
+use rocket::http::{ContentType, Cookie, Header, Status};
+use rocket::local::asynchronous::Client;
+use server::db::migrations::{prepare_schema, POSTGRES_MIGRATOR};
+use server::db::store::Stores;
+use sqlx::postgres::PgPoolOptions;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// JWTs expire after this many seconds in the tests
+pub const JWT_EXPIRE_TIME: u64 = 2;
+
+/// The client allowed to call /introspect and /revoke in the tests
+pub const CLIENT_ID: &str = "resource-server";
+pub const CLIENT_SECRET: &str = "integration-test-client-secret";
+
+/// A client that may call /introspect but is not listed in OAUTH_REVOKE_CLIENTS
+pub const INTROSPECT_CLIENT_ID: &str = "reporting-service";
+pub const INTROSPECT_CLIENT_SECRET: &str = "integration-test-introspect-secret";
+
+static ENV: Once = Once::new();
+
+/// Sets the environment the server reads its settings from
+pub fn setup_env() {
+    ENV.call_once(|| {
+        dotenv().ok();
+        // SAFETY: every test calls this before anything reads the environment, and Once blocks the other tests until it is done
+        unsafe {
+            env::set_var("JWT_PRIVATE_KEY", "integration-test-jwt-key");
+            env::set_var("JWT_EXPIRE_TIME", JWT_EXPIRE_TIME.to_string());
+            env::set_var("REFRESH_TOKEN_KEY", "integration-test-refresh-key");
+            env::set_var("OAUTH_CLIENTS", format!("{}:{},{}:{}", CLIENT_ID, CLIENT_SECRET, INTROSPECT_CLIENT_ID, INTROSPECT_CLIENT_SECRET));
+            env::set_var("OAUTH_REVOKE_CLIENTS", CLIENT_ID);
+        }
+    });
+}
+
+/// The stores the tests run against. These are in memory unless TEST_DATABASE_URL points to a PostgreSQL database, which is migrated first. Tests built with the sqlite feature use a migrated in-memory SQLite database instead of the memory store
+pub async fn stores() -> Stores {
+    setup_env();
+
+    match env::var("TEST_DATABASE_URL") {
+        Ok(db_url) => {
+            let pool = PgPoolOptions::new()
+                .max_connections(2)
+                .connect(&db_url)
+                .await.expect("Database pool error");
+            prepare_schema(&POSTGRES_MIGRATOR, &pool, true).await.expect("Failed to migrate the test database");
+            Stores::postgres(pool)
+        },
+        Err(_) => local_stores().await
+    }
+}
+
+#[cfg(feature = "sqlite")]
+async fn local_stores() -> Stores {
+    use server::db::migrations::SQLITE_MIGRATOR;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    // Every connection to sqlite::memory: opens a database of its own, so the pool keeps a single connection open
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await.expect("Database pool error");
+    prepare_schema(&SQLITE_MIGRATOR, &pool, true).await.expect("Failed to migrate the test database");
+    Stores::sqlite(pool)
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn local_stores() -> Stores {
+    Stores::memory()
+}
+
+pub async fn client_with_stores(stores: Stores) -> Client {
+    Client::untracked(server::build_rocket(stores)).await.expect("valid rocket instance")
+}
+
+pub async fn client() -> Client {
+    client_with_stores(stores().await).await
+}
+
+/// A client that keeps cookies between requests like a browser
+pub async fn tracked_client() -> Client {
+    Client::tracked(server::build_rocket(stores().await)).await.expect("valid rocket instance")
+}
+
+/// A username no other test run has used
+pub fn unique_username() -> String {
+    format!("test-{}", Uuid::new_v4())
+}
+
+pub fn credentials(username: &str, password: &str) -> String {
+    serde_json::json!({ "username": username, "password": password }).to_string()
+}
+
+pub async fn create_account(client: &Client, username: &str) {
+    let response = client.post("/create")
+        .header(ContentType::JSON)
+        .body(credentials(username, PASSWORD))
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Created);
+}
+
+/// A signed in session, holding the JWT and the refresh token cookie returned to the client
+pub struct Session {
+    pub token: String,
+    pub refresh_cookie: Cookie<'static>
+}
+
+pub async fn signin(client: &Client, username: &str) -> Session {
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(username, PASSWORD))
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let refresh_cookie = response.cookies().get_private("refresh_token").expect("refresh token cookie");
+    let body = response.into_string().await.unwrap();
+
+    Session { token: parse_token(&body), refresh_cookie }
+}
+
+/// Calls the refresh route, returning the status and the new session if it succeeded
+pub async fn refresh(client: &Client, token: &str, refresh_cookie: &Cookie<'static>) -> (Status, Option<Session>) {
+    let response = client.post("/refresh")
+        .header(bearer(token))
+        .private_cookie(refresh_cookie.clone())
+        .dispatch().await;
+
+    let status = response.status();
+    if status != Status::Ok {
+        return (status, None);
+    }
+
+    let refresh_cookie = response.cookies().get_private("refresh_token").expect("rotated refresh token cookie");
+    let body = response.into_string().await.unwrap();
+
+    (status, Some(Session { token: parse_token(&body), refresh_cookie }))
+}
+
+pub async fn test_route_status(client: &Client, token: &str) -> Status {
+    client.get("/test").header(bearer(token)).dispatch().await.status()
+}
+
+pub fn bearer(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
+
+/// HTTP Basic credentials for a client calling /introspect and /revoke
+pub fn client_credentials(client_id: &str, secret: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Basic {}", STANDARD.encode(format!("{}:{}", client_id, secret))))
+}
+
+fn parse_token(body: &str) -> String {
+    let body: Value = serde_json::from_str(body).expect("JSON response body");
+    body["access_token"].as_str().expect("access_token in response body").to_string()
+}
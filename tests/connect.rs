@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use server::db::connect::{connect_with_retry, PoolConfig};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Postgres;
+
+fn config(connect_attempts: u32) -> PoolConfig {
+    PoolConfig {
+        max_connections: 5,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(30),
+        idle_timeout: None,
+        statement_cache_capacity: 100,
+        connect_attempts,
+        connect_backoff: Duration::from_millis(1)
+    }
+}
+
+#[test]
+fn retry_delay_doubles_up_to_a_limit() {
+    let config = PoolConfig { connect_backoff: Duration::from_millis(500), ..config(5) };
+
+    assert_eq!(config.retry_delay(1), Duration::from_millis(500));
+    assert_eq!(config.retry_delay(2), Duration::from_secs(1));
+    assert_eq!(config.retry_delay(3), Duration::from_secs(2));
+    assert_eq!(config.retry_delay(10), Duration::from_secs(30));
+    assert_eq!(config.retry_delay(100), Duration::from_secs(30));
+}
+
+#[rocket::async_test]
+async fn connecting_is_retried_until_it_succeeds() {
+    let attempts = AtomicU32::new(0);
+
+    let pool = connect_with_retry(&config(5), || async {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Err(sqlx::Error::PoolTimedOut),
+            _ => Ok(PgPoolOptions::new().connect_lazy("postgres://postgres@127.0.0.1:1/auth").unwrap())
+        }
+    }).await;
+
+    assert!(pool.is_ok());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[rocket::async_test]
+async fn connecting_gives_up_after_the_last_attempt() {
+    let attempts = AtomicU32::new(0);
+
+    let pool = connect_with_retry::<Postgres, _, _>(&config(3), || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err(sqlx::Error::PoolTimedOut)
+    }).await;
+
+    assert!(matches!(pool, Err(sqlx::Error::PoolTimedOut)));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
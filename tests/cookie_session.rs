@@ -0,0 +1,117 @@
+mod common;
+
+use std::env;
+use std::sync::Once;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use serde_json::Value;
+
+use common::{bearer, create_account, credentials, tracked_client, unique_username, PASSWORD};
+
+static COOKIE_MODE: Once = Once::new();
+
+async fn cookie_mode_client() -> Client {
+    COOKIE_MODE.call_once(|| {
+        // SAFETY: every test in this file calls this before building the server, and Once blocks the other tests until it is done
+        unsafe { env::set_var("ACCESS_TOKEN_COOKIE", "true"); }
+    });
+
+    tracked_client().await
+}
+
+async fn cookie_signin(client: &Client, username: &str) {
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(username, PASSWORD))
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert!(body.get("access_token").is_none());
+    assert_eq!(body["token_type"], "Bearer");
+}
+
+fn csrf_token(client: &Client) -> String {
+    client.cookies().get("csrf_token").expect("CSRF token cookie").value().to_string()
+}
+
+#[rocket::async_test]
+async fn signin_delivers_token_in_http_only_cookie() {
+    let client = cookie_mode_client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    cookie_signin(&client, &username).await;
+
+    let access_cookie = client.cookies().get_private("access_token").expect("access token cookie");
+    assert_eq!(access_cookie.http_only(), Some(true));
+    assert_ne!(client.cookies().get("csrf_token").unwrap().http_only(), Some(true));
+
+    let response = client.get("/test").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn refresh_replaces_access_cookie() {
+    let client = cookie_mode_client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    cookie_signin(&client, &username).await;
+
+    let response = client.post("/refresh").dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client.post("/refresh")
+        .header(Header::new("X-CSRF-Token", csrf_token(&client)))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.cookies().get_private("access_token").is_some());
+
+    assert_eq!(client.get("/test").dispatch().await.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn logout_requires_csrf_token() {
+    let client = cookie_mode_client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    cookie_signin(&client, &username).await;
+
+    let response = client.post("/logout").dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client.post("/logout")
+        .header(Header::new("X-CSRF-Token", "not-the-token"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client.post("/logout")
+        .header(Header::new("X-CSRF-Token", csrf_token(&client)))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    assert!(client.cookies().get_private("access_token").is_none());
+}
+
+#[rocket::async_test]
+async fn logout_revokes_refresh_session() {
+    let client = cookie_mode_client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    cookie_signin(&client, &username).await;
+
+    let refresh_cookie = client.cookies().get_private("refresh_token").unwrap();
+    let access_cookie = client.cookies().get_private("access_token").unwrap();
+
+    let response = client.post("/logout")
+        .header(Header::new("X-CSRF-Token", csrf_token(&client)))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // Replaying the cookies from before the logout no longer refreshes
+    let response = client.post("/refresh")
+        .header(bearer(access_cookie.value()))
+        .private_cookie(refresh_cookie)
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
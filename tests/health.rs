@@ -0,0 +1,48 @@
+mod common;
+
+use rocket::http::Status;
+use serde_json::Value;
+use server::db::store::Stores;
+use sqlx::postgres::PgPoolOptions;
+
+use common::{client, client_with_stores, setup_env};
+
+#[rocket::async_test]
+async fn health_and_readiness_are_reported() {
+    let client = client().await;
+
+    let response = client.get("/healthz").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+
+    let response = client.get("/readyz").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["status"], "ready");
+    for check in ["database", "migrations", "signing_keys"] {
+        assert_eq!(body["checks"][check]["status"], "ok", "{} check", check);
+        assert!(body["checks"][check].get("error").is_none());
+    }
+}
+
+#[rocket::async_test]
+async fn unreachable_database_is_not_ready() {
+    setup_env();
+    // Nothing listens on port 1, so every connection is refused
+    let pool = PgPoolOptions::new().connect_lazy("postgres://postgres@127.0.0.1:1/auth").unwrap();
+    let client = client_with_stores(Stores::postgres(pool)).await;
+
+    let response = client.get("/healthz").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get("/readyz").dispatch().await;
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["database"]["status"], "error");
+    // The connection error is logged rather than returned
+    assert!(body["checks"]["database"].get("error").is_none());
+    assert_eq!(body["checks"]["migrations"]["status"], "error");
+    assert_eq!(body["checks"]["signing_keys"]["status"], "ok");
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use serde_json::Value;
+
+use common::{bearer, client, client_credentials, create_account, refresh, signin, test_route_status, unique_username, CLIENT_ID, CLIENT_SECRET};
+
+/// Introspects a token as the test client, returning the response body
+async fn introspect(client: &Client, token: &str, hint: Option<&str>) -> Value {
+    let body = match hint {
+        Some(hint) => format!("token={}&token_type_hint={}", token, hint),
+        None => format!("token={}", token)
+    };
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .header(client_credentials(CLIENT_ID, CLIENT_SECRET))
+        .body(body)
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+}
+
+#[rocket::async_test]
+async fn introspection_requires_client_credentials() {
+    let client = client().await;
+
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .body("token=anything")
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .header(client_credentials(CLIENT_ID, "wrong secret"))
+        .body("token=anything")
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // A user's JWT is not a client credential
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .header(bearer(&session.token))
+        .body(format!("token={}", session.token))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn active_tokens_describe_their_owner() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let body = introspect(&client, &session.token, None).await;
+    assert_eq!(body["active"], true);
+    assert_eq!(body["username"], username.as_str());
+    assert_eq!(body["token_type"], "access_token");
+    assert_eq!(body["scope"], "");
+    assert!(body["sub"].as_str().unwrap().parse::<i64>().is_ok());
+    assert!(body["exp"].as_i64().unwrap() > chrono::Utc::now().timestamp());
+
+    let body = introspect(&client, session.refresh_cookie.value(), Some("refresh_token")).await;
+    assert_eq!(body["active"], true);
+    assert_eq!(body["username"], username.as_str());
+    assert_eq!(body["token_type"], "refresh_token");
+
+    // The hint only changes the lookup order
+    let body = introspect(&client, session.refresh_cookie.value(), Some("access_token")).await;
+    assert_eq!(body["token_type"], "refresh_token");
+
+    let body = introspect(&client, "not-a-token", None).await;
+    assert_eq!(body, serde_json::json!({ "active": false }));
+}
+
+#[rocket::async_test]
+async fn revoked_and_rotated_tokens_are_inactive() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let (status, refreshed) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    let refreshed = refreshed.unwrap();
+    assert_eq!(introspect(&client, session.refresh_cookie.value(), None).await["active"], false);
+    assert_eq!(introspect(&client, refreshed.refresh_cookie.value(), None).await["active"], true);
+
+    let response = client.post("/logout").header(bearer(&refreshed.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    assert_eq!(introspect(&client, &refreshed.token, None).await, serde_json::json!({ "active": false }));
+    assert_eq!(introspect(&client, refreshed.refresh_cookie.value(), None).await["active"], false);
+    // AuthUser agrees, as logging out adds the JWT to the denylist
+    assert_eq!(test_route_status(&client, &refreshed.token).await, Status::Unauthorized);
+}
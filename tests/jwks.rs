@@ -0,0 +1,74 @@
+mod common;
+
+use std::env;
+use std::sync::Once;
+
+use auth_guard::claims::JwtStatus;
+use auth_guard::verify::Verifier;
+use jsonwebtoken::decode_header;
+use jsonwebtoken::jwk::JwkSet;
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+
+use common::{client, create_account, signin, test_route_status, unique_username};
+
+static ES256: Once = Once::new();
+
+/// A client for a server signing with the ES256 test key. Every test in this file uses it, as the key is read once per process
+async fn es256_client() -> Client {
+    ES256.call_once(|| {
+        // SAFETY: every test calls this before anything reads the environment, and Once blocks the other tests until it is done
+        unsafe {
+            env::set_var("JWT_ALGORITHM", "ES256");
+            env::set_var("JWT_PRIVATE_KEY_FILE", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/keys/es256.pem"));
+        }
+    });
+
+    client().await
+}
+
+async fn published_keys(client: &Client) -> JwkSet {
+    let response = client.get("/.well-known/jwks.json").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+}
+
+#[rocket::async_test]
+async fn only_the_public_key_is_published() {
+    let client = es256_client().await;
+
+    let response = client.get("/.well-known/jwks.json").dispatch().await;
+    let body: serde_json::Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+    let keys = body["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["kty"], "EC");
+    assert_eq!(keys[0]["crv"], "P-256");
+    assert_eq!(keys[0]["alg"], "ES256");
+    assert_eq!(keys[0]["use"], "sig");
+    assert!(keys[0]["kid"].is_string());
+    assert!(keys[0].get("d").is_none());
+}
+
+#[rocket::async_test]
+async fn tokens_verify_against_the_published_keys() {
+    let client = es256_client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Ok);
+
+    let keys = published_keys(&client).await;
+    let header = decode_header(&session.token).unwrap();
+    assert_eq!(header.alg, jsonwebtoken::Algorithm::ES256);
+    assert_eq!(header.kid, keys.keys[0].common.key_id);
+
+    let verifier = Verifier::from_jwk_set(&keys);
+    match verifier.verify(&session.token).await.unwrap() {
+        JwtStatus::Valid(claims) => assert_eq!(claims.username, username),
+        JwtStatus::Expired(_) => panic!("the token should not have expired")
+    }
+
+    // A token signed with the shared secret of an HS256 setup is refused
+    assert!(Verifier::from_secret(b"integration-test-jwt-key").verify(&session.token).await.is_err());
+}
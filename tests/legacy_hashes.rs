@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs;
+
+use rocket::http::{ContentType, Status};
+use server::cli::import_users::{import_users, read_records, ImportRecord};
+use server::crypto::hash::PasswordHash;
+use uuid::Uuid;
+
+use common::{client_with_stores, create_account, credentials, stores, unique_username, PASSWORD};
+
+/// Known-answer vectors for the formats imported from other systems. The bcrypt vector is from the crypt_blowfish test suite and the others were made with Python's hashlib for PASSWORD
+const BCRYPT: (&str, &str) = ("U*U", "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW");
+const SCRYPT: &str = "$scrypt$ln=4,r=8,p=1$MDEyMzQ1Njc4OWFiY2RlZg$0Pz6RuN9WCY6OfE3FYH5Myx0JqvNjul84VbFyWLBf4g";
+const PBKDF2: &str = "$pbkdf2-sha256$i=1000,l=32$MDEyMzQ1Njc4OWFiY2RlZg$yqSq2SygY1sB4EcH9f2FG0JTMES+wqLsOT5YmiRBplI";
+const PASSLIB_PBKDF2: &str = "$pbkdf2-sha256$29000$MDEyMzQ1Njc4OWFiY2RlZg$vajIaozrb7q4x.G3R5Y.FIe07ZH3QEjPYV1bs8kEikU";
+
+fn verifies(hash: &str, password: &str) -> bool {
+    PasswordHash::from(hash.to_string()).verify(&password.to_string()).unwrap()
+}
+
+#[test]
+fn legacy_hashes_verify_known_answers() {
+    let (bcrypt_password, bcrypt_hash) = BCRYPT;
+    assert!(verifies(bcrypt_hash, bcrypt_password));
+    assert!(!verifies(bcrypt_hash, "U*V"));
+
+    for hash in [SCRYPT, PBKDF2, PASSLIB_PBKDF2] {
+        assert!(PasswordHash::from(hash.to_string()).is_supported(), "{} should be supported", hash);
+        assert!(verifies(hash, PASSWORD), "{} should verify", hash);
+        assert!(!verifies(hash, "wrong password"), "{} should refuse a wrong password", hash);
+        assert!(PasswordHash::from(hash.to_string()).needs_rehash());
+    }
+
+    assert!(!PasswordHash::from("5f4dcc3b5aa765d61d8327deb882cf99".to_string()).is_supported());
+    assert!(!PasswordHash::from("$pbkdf2-sha256$many$MDEy$vajI".to_string()).is_supported());
+}
+
+#[rocket::async_test]
+async fn imported_users_are_upgraded_to_argon2id_on_first_signin() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    let summary = import_users(vec![ImportRecord { username: username.clone(), password_hash: SCRYPT.to_string() }], stores.users.as_ref()).await;
+    assert_eq!(summary.imported, 1);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, "wrong password"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(stores.users.read_user(&username).await.unwrap()[0].password, SCRYPT);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let upgraded = stores.users.read_user(&username).await.unwrap().remove(0).password;
+    assert!(upgraded.starts_with("$argon2id$"));
+    assert!(verifies(&upgraded, PASSWORD));
+}
+
+#[rocket::async_test]
+async fn import_skips_existing_users_and_unsupported_hashes() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let existing = unique_username();
+    create_account(&client, &existing).await;
+    let (new_user, unsupported) = (unique_username(), unique_username());
+
+    let path = std::env::temp_dir().join(format!("import-{}.csv", Uuid::new_v4()));
+    fs::write(&path, format!(
+        "username,password_hash\n{},\"{}\"\n{},\"{}\"\n{},5f4dcc3b5aa765d61d8327deb882cf99\n",
+        new_user, PBKDF2, existing, BCRYPT.1, unsupported
+    )).unwrap();
+    let records = read_records(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let summary = import_users(records, stores.users.as_ref()).await;
+    assert_eq!(summary.imported, 1);
+    assert_eq!(summary.existing, 1);
+    assert_eq!(summary.unsupported, vec![unsupported.clone()]);
+    assert!(summary.failed.is_empty());
+
+    assert_eq!(stores.users.read_user(&new_user).await.unwrap()[0].password, PBKDF2);
+    // The existing account keeps its own password
+    assert_ne!(stores.users.read_user(&existing).await.unwrap()[0].password, BCRYPT.1);
+    assert!(stores.users.read_user(&unsupported).await.unwrap().is_empty());
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use server::logs::rotation::{rotated_files, RotatingFile, RotationConfig, RotationInterval};
+
+/// A log file path in a new temporary directory
+fn log_path() -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("log-rotation-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&directory).unwrap();
+    directory.join("server.log")
+}
+
+fn config(path: &Path, max_size: u64, retention: usize, compress: bool) -> RotationConfig {
+    RotationConfig { path: path.to_path_buf(), max_size, interval: RotationInterval::Never, retention, compress }
+}
+
+fn read_gzip(path: &Path) -> String {
+    let mut contents = String::new();
+    GzDecoder::new(fs::File::open(path).unwrap()).read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn file_is_rotated_when_it_would_exceed_the_max_size() {
+    let path = log_path();
+    let mut file = RotatingFile::open(config(&path, 20, 5, false)).unwrap();
+
+    file.write_all(b"first line 123\n").unwrap();
+    // Lines are never split, even when written in pieces
+    file.write_all(b"second").unwrap();
+    file.write_all(b" line\n").unwrap();
+    file.flush().unwrap();
+
+    let rotated = rotated_files(&path).unwrap();
+    assert_eq!(rotated.len(), 1);
+    assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "first line 123\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second line\n");
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn rotations_from_several_threads_keep_the_retention_count() {
+    let path = log_path();
+    let file = RotatingFile::open(config(&path, 16, 3, true)).unwrap();
+
+    let writers: Vec<_> = (0..4).map(|writer| {
+        let mut file = file.clone();
+        std::thread::spawn(move || {
+            // Each line is one write, as writeln! would split it into pieces that other threads could write between
+            for line in 0..25 {
+                file.write_all(format!("writer {} line {}\n", writer, line).as_bytes()).unwrap();
+            }
+        })
+    }).collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // The last rotations finish in the background, so wait until only compressed files are left
+    let mut rotated = rotated_files(&path).unwrap();
+    for _ in 0..100 {
+        if rotated.iter().all(|path| path.extension().unwrap() == "gz") && rotated.len() <= 3 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        rotated = rotated_files(&path).unwrap();
+    }
+    assert_eq!(rotated.len(), 3);
+    assert!(rotated.iter().all(|path| read_gzip(path).starts_with("writer ")));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn rotated_files_are_compressed_and_pruned() {
+    let path = log_path();
+    let mut file = RotatingFile::open(config(&path, 0, 2, true)).unwrap();
+
+    for line in 1..=4 {
+        writeln!(file, "line {}", line).unwrap();
+        file.rotate().unwrap();
+    }
+
+    let mut rotated = rotated_files(&path).unwrap();
+    rotated.sort_by_key(|path| path.to_string_lossy().trim_end_matches(".gz").to_string());
+    assert_eq!(rotated.len(), 2);
+    assert!(rotated.iter().all(|path| path.extension().unwrap() == "gz"));
+    assert_eq!(read_gzip(&rotated[0]), "line 3\n");
+    assert_eq!(read_gzip(&rotated[1]), "line 4\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn file_is_reopened_after_being_moved_away() {
+    let path = log_path();
+    let moved = path.with_file_name("server.log.old");
+    let mut file = RotatingFile::open(config(&path, 0, 2, false)).unwrap();
+
+    writeln!(file, "before").unwrap();
+    // As logrotate does before sending SIGHUP
+    fs::rename(&path, &moved).unwrap();
+    file.reopen().unwrap();
+    writeln!(file, "after").unwrap();
+
+    assert_eq!(fs::read_to_string(&moved).unwrap(), "before\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "after\n");
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn rotation_interval_is_parsed() {
+    assert_eq!("Hourly".parse::<RotationInterval>(), Ok(RotationInterval::Hourly));
+    assert_eq!("daily".parse::<RotationInterval>(), Ok(RotationInterval::Daily));
+    assert_eq!("never".parse::<RotationInterval>(), Ok(RotationInterval::Never));
+    assert!("weekly".parse::<RotationInterval>().is_err());
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use chrono::{Local, TimeZone};
+use log::{Level, LevelFilter};
+use rocket::http::{ContentType, Header, Status};
+use serde_json::Value;
+use server::logs::log_errors::{format_line, LogFormat, LogLevels};
+use server::logs::redact::redact;
+
+use common::{client, create_account, credentials, unique_username, PASSWORD};
+
+#[test]
+fn secrets_are_redacted() {
+    assert_eq!(redact(r#"UserArgs { username: "drew", password: "hunter2" }"#), r#"UserArgs { username: "drew", password: "[REDACTED]" }"#);
+    assert_eq!(redact(r#"{"refresh_token":"abc\"def","user":1}"#), r#"{"refresh_token":"[REDACTED]","user":1}"#);
+    assert_eq!(redact("Cookie: refresh_token=abc; other=1"), "Cookie: [REDACTED]; other=1");
+    assert_eq!(redact("REFRESH_TOKEN_KEY=0123abcd"), "REFRESH_TOKEN_KEY=[REDACTED]");
+    assert_eq!(redact("Authorization header Bearer abc.def-ghi sent"), "Authorization header Bearer [REDACTED] sent");
+    assert_eq!(redact("token eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOjF9.c2lnbmF0dXJl expired"), "token [REDACTED] expired");
+
+    let message = "Refresh token reuse detected for user 5, revoking session";
+    assert_eq!(redact(message), message);
+}
+
+#[test]
+fn lines_are_formatted_as_text_or_json() {
+    let time = Local.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+
+    let line = format_line(LogFormat::Text, time, Level::Warn, "server::routes", Some("abc-123"), "password=secret");
+    assert_eq!(line, "[2025-01-02 03:04:05][WARN][server::routes][abc-123] password=[REDACTED]");
+    let line = format_line(LogFormat::Text, time, Level::Info, "rocket", None, "Launching");
+    assert_eq!(line, "[2025-01-02 03:04:05][INFO][rocket] Launching");
+
+    let line: Value = serde_json::from_str(&format_line(LogFormat::Json, time, Level::Error, "server::db", Some("abc-123"), "failed \"badly\"")).unwrap();
+    assert_eq!(line["level"], "ERROR");
+    assert_eq!(line["target"], "server::db");
+    assert_eq!(line["request_id"], "abc-123");
+    assert_eq!(line["message"], "failed \"badly\"");
+    assert!(line["timestamp"].as_str().unwrap().starts_with("2025-01-02T03:04:05.000"));
+
+    let line: Value = serde_json::from_str(&format_line(LogFormat::Json, time, Level::Info, "rocket", None, "Launching")).unwrap();
+    assert!(line.get("request_id").is_none());
+}
+
+#[test]
+fn log_levels_are_parsed() {
+    let levels: LogLevels = "warn, rocket=error,server::db=debug".parse().unwrap();
+    assert_eq!(levels.default, LevelFilter::Warn);
+    assert_eq!(levels.modules, vec![("rocket".to_string(), LevelFilter::Error), ("server::db".to_string(), LevelFilter::Debug)]);
+
+    let levels: LogLevels = "sqlx=off".parse().unwrap();
+    assert_eq!(levels.default, LevelFilter::Info);
+
+    assert!("loud".parse::<LogLevels>().is_err());
+    assert!("rocket=loud".parse::<LogLevels>().is_err());
+    assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+    assert!("xml".parse::<LogFormat>().is_err());
+}
+
+#[rocket::async_test]
+async fn request_ids_are_echoed_and_used_as_correlation_ids() {
+    let client = client().await;
+
+    let response = client.get("/test").dispatch().await;
+    let request_id = response.headers().get_one("X-Request-Id").expect("request id header");
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+
+    let response = client.get("/test").header(Header::new("X-Request-Id", "from-proxy.42")).dispatch().await;
+    assert_eq!(response.headers().get_one("X-Request-Id"), Some("from-proxy.42"));
+
+    // Ids that could break a log line are replaced
+    let response = client.get("/test").header(Header::new("X-Request-Id", "bad id\nforged line")).dispatch().await;
+    assert_ne!(response.headers().get_one("X-Request-Id"), Some("bad id\nforged line"));
+
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let response = client.post("/create")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Request-Id", "duplicate-signup"))
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.headers().get_one("X-Request-Id"), Some("duplicate-signup"));
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"]["correlation_id"], "duplicate-signup");
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use server::errors::auth_error::AuthError;
+
+use common::{bearer, client, create_account, credentials, refresh, signin, stores, unique_username};
+
+/// Reads the value of a sample, such as `auth_signin_failures_total{reason="incorrect_password"}`, or 0 if it has not been recorded yet
+async fn sample(client: &Client, name: &str) -> f64 {
+    let response = client.get("/metrics").dispatch().await;
+    let body = response.into_string().await.unwrap();
+
+    body.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .map(|value| value.parse().unwrap())
+        .unwrap_or(0.0)
+}
+
+#[rocket::async_test]
+async fn metrics_are_exported_in_the_prometheus_format() {
+    let client = client().await;
+
+    let response = client.get("/metrics").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type().unwrap().to_string(), "text/plain; version=0.0.4");
+    let body = response.into_string().await.unwrap();
+    assert!(body.contains("# TYPE auth_signin_successes_total counter"));
+    assert!(body.contains("# TYPE auth_rate_limited_requests_total counter"));
+}
+
+#[rocket::async_test]
+async fn auth_activity_is_counted() {
+    // Other tests run alongside this one and share the counters, so only increases are checked
+    let client = client().await;
+    let created = sample(&client, "auth_accounts_created_total").await;
+    let successes = sample(&client, "auth_signin_successes_total").await;
+    let failures = sample(&client, r#"auth_signin_failures_total{reason="incorrect_password"}"#).await;
+    let refreshes = sample(&client, r#"auth_refreshes_total{result="success"}"#).await;
+    let invalid_tokens = sample(&client, r#"auth_token_validation_failures_total{reason="invalid"}"#).await;
+    let hashes = sample(&client, r#"auth_password_hash_duration_seconds_count{operation="hash"}"#).await;
+    let verifies = sample(&client, r#"auth_password_hash_duration_seconds_count{operation="verify"}"#).await;
+
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, "wrong password"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let session = signin(&client, &username).await;
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    let response = client.get("/test").header(bearer("not.a.token")).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    assert!(sample(&client, "auth_accounts_created_total").await > created);
+    assert!(sample(&client, "auth_signin_successes_total").await > successes);
+    assert!(sample(&client, r#"auth_signin_failures_total{reason="incorrect_password"}"#).await > failures);
+    assert!(sample(&client, r#"auth_refreshes_total{result="success"}"#).await > refreshes);
+    assert!(sample(&client, r#"auth_token_validation_failures_total{reason="invalid"}"#).await > invalid_tokens);
+    assert!(sample(&client, r#"auth_password_hash_duration_seconds_count{operation="hash"}"#).await > hashes);
+    assert!(sample(&client, r#"auth_password_hash_duration_seconds_count{operation="verify"}"#).await >= verifies + 2.0);
+}
+
+#[rocket::async_test]
+async fn refused_refreshes_are_counted() {
+    let client = client().await;
+    let missing = sample(&client, r#"auth_refreshes_total{result="missing"}"#).await;
+    let invalid = sample(&client, r#"auth_refreshes_total{result="invalid"}"#).await;
+
+    let (first, second) = (unique_username(), unique_username());
+    create_account(&client, &first).await;
+    create_account(&client, &second).await;
+    let first_session = signin(&client, &first).await;
+    let second_session = signin(&client, &second).await;
+
+    let response = client.post("/refresh").header(bearer(&first_session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    // Another user's refresh token is refused
+    let (status, _) = refresh(&client, &first_session.token, &second_session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    assert!(sample(&client, r#"auth_refreshes_total{result="missing"}"#).await > missing);
+    assert!(sample(&client, r#"auth_refreshes_total{result="invalid"}"#).await > invalid);
+}
+
+#[rocket::get("/limited")]
+fn limited() -> Result<(), AuthError> {
+    Err(AuthError::RateLimited)
+}
+
+#[rocket::async_test]
+async fn rate_limited_requests_are_counted() {
+    let rocket = server::build_rocket(stores().await).mount("/", rocket::routes![limited]);
+    let client = Client::untracked(rocket).await.unwrap();
+    let rate_limited = sample(&client, "auth_rate_limited_requests_total").await;
+
+    let response = client.get("/limited").dispatch().await;
+    assert_eq!(response.status(), Status::TooManyRequests);
+    assert!(response.into_string().await.unwrap().contains("rate_limited"));
+
+    assert!(sample(&client, "auth_rate_limited_requests_total").await > rate_limited);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "sqlite")]
+
+use server::db::migrations::{check_schema, prepare_schema, SchemaError, SQLITE_MIGRATOR};
+use sqlx::SqlitePool;
+
+async fn pool() -> SqlitePool {
+    SqlitePool::connect("sqlite::memory:").await.expect("Database pool error")
+}
+
+#[rocket::async_test]
+async fn empty_database_is_refused_until_migrated() {
+    let pool = pool().await;
+
+    let result = prepare_schema(&SQLITE_MIGRATOR, &pool, false).await;
+    assert!(matches!(result, Err(SchemaError::Pending(1))));
+
+    prepare_schema(&SQLITE_MIGRATOR, &pool, true).await.unwrap();
+    prepare_schema(&SQLITE_MIGRATOR, &pool, false).await.unwrap();
+}
+
+#[rocket::async_test]
+async fn newer_database_is_refused() {
+    let pool = pool().await;
+    prepare_schema(&SQLITE_MIGRATOR, &pool, true).await.unwrap();
+
+    sqlx::query("INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (9999, 'from a newer server', true, x'00', 0)")
+        .execute(&pool)
+        .await.unwrap();
+
+    let mut conn = pool.acquire().await.unwrap();
+    let result = check_schema(&SQLITE_MIGRATOR, &mut *conn).await;
+    assert!(matches!(result, Err(SchemaError::Unknown(9999))));
+}
+
+#[rocket::async_test]
+async fn changed_migration_is_refused() {
+    let pool = pool().await;
+    prepare_schema(&SQLITE_MIGRATOR, &pool, true).await.unwrap();
+
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = 1")
+        .execute(&pool)
+        .await.unwrap();
+
+    let result = prepare_schema(&SQLITE_MIGRATOR, &pool, false).await;
+    assert!(matches!(result, Err(SchemaError::Modified(1))));
+}
+
+#[rocket::async_test]
+async fn schema_from_before_the_migrations_is_refused() {
+    let pool = pool().await;
+    sqlx::raw_sql("CREATE TABLE users (user_id INTEGER PRIMARY KEY, username TEXT NOT NULL UNIQUE, password TEXT NOT NULL); \
+                   CREATE TABLE refresh_tokens (user_id INTEGER PRIMARY KEY REFERENCES users (user_id), refresh_token TEXT NOT NULL);")
+        .execute(&pool)
+        .await.unwrap();
+
+    let result = prepare_schema(&SQLITE_MIGRATOR, &pool, true).await;
+    assert!(matches!(result, Err(SchemaError::PreMigrationSchema)));
+
+    // Dropping the old table lets the migrations run, keeping the users
+    sqlx::raw_sql("DROP TABLE refresh_tokens").execute(&pool).await.unwrap();
+    prepare_schema(&SQLITE_MIGRATOR, &pool, true).await.unwrap();
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::collections::HashMap;
+
+use argon2::Params;
+use server::crypto::hash::{HashConfig, PasswordHash};
+use server::db::user::UserArgs;
+
+use common::{client_with_stores, signin, stores, unique_username, PASSWORD};
+
+/// Cheap Argon2 costs so the tests run quickly
+fn params(m_cost: u32) -> Params {
+    Params::new(m_cost, 1, 1, None).unwrap()
+}
+
+fn config(m_cost: u32, pepper: Option<(&str, &str)>, previous_peppers: &[(&str, &str)]) -> HashConfig {
+    HashConfig::new(
+        params(m_cost),
+        pepper.map(|(id, secret)| (id.to_string(), secret.to_string())),
+        previous_peppers.iter().map(|(id, secret)| (id.to_string(), secret.to_string())).collect::<HashMap<_, _>>()
+    ).unwrap()
+}
+
+#[test]
+fn changing_argon2_params_needs_a_rehash() {
+    let old = config(8, None, &[]);
+    let hash = PasswordHash::hash_with(PASSWORD, &old).unwrap();
+
+    assert!(!hash.needs_rehash_with(&old));
+    assert!(hash.needs_rehash_with(&config(16, None, &[])));
+    assert!(hash.verify_with(&PASSWORD.to_string(), &config(16, None, &[])).unwrap());
+}
+
+#[test]
+fn peppered_hashes_need_the_pepper() {
+    let peppered = config(8, Some(("1", "first-pepper")), &[]);
+    let hash = PasswordHash::hash_with(PASSWORD, &peppered).unwrap();
+
+    assert!(hash.verify_with(&PASSWORD.to_string(), &peppered).unwrap());
+    assert!(!hash.verify_with(&"wrong password".to_string(), &peppered).unwrap());
+    assert!(!hash.needs_rehash_with(&peppered));
+
+    // The same id with another secret, or no pepper at all, does not match
+    assert!(!hash.verify_with(&PASSWORD.to_string(), &config(8, Some(("1", "another-pepper")), &[])).unwrap());
+    assert!(!hash.verify_with(&PASSWORD.to_string(), &config(8, None, &[])).unwrap());
+}
+
+#[test]
+fn hashes_verify_after_the_pepper_is_rotated() {
+    let first = config(8, Some(("1", "first-pepper")), &[]);
+    let hash = PasswordHash::hash_with(PASSWORD, &first).unwrap();
+
+    // Hashes made with the old pepper are checked with it by their key id, and rehashed with the new one
+    let rotated = config(8, Some(("2", "second-pepper")), &[("1", "first-pepper")]);
+    assert!(hash.verify_with(&PASSWORD.to_string(), &rotated).unwrap());
+    assert!(hash.needs_rehash_with(&rotated));
+
+    let rehashed = PasswordHash::hash_with(PASSWORD, &rotated).unwrap();
+    assert!(rehashed.verify_with(&PASSWORD.to_string(), &rotated).unwrap());
+    assert!(!rehashed.needs_rehash_with(&rotated));
+
+    // Once the old pepper is dropped its hashes no longer verify, while the rehashed one does
+    let dropped = config(8, Some(("2", "second-pepper")), &[]);
+    assert!(!hash.verify_with(&PASSWORD.to_string(), &dropped).unwrap());
+    assert!(rehashed.verify_with(&PASSWORD.to_string(), &dropped).unwrap());
+}
+
+#[rocket::async_test]
+async fn signin_rehashes_outdated_hashes() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    let outdated = PasswordHash::hash_with(PASSWORD, &config(8, None, &[])).unwrap();
+    stores.users.write_user(&UserArgs { username: username.clone(), password: outdated.value() }).await.unwrap();
+
+    signin(&client, &username).await;
+
+    let user = stores.users.read_user(&username).await.unwrap().remove(0);
+    let hash = PasswordHash::from(user.password);
+    assert_ne!(hash.value(), outdated.value());
+    assert!(!hash.needs_rehash());
+    signin(&client, &username).await;
+}
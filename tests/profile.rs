@@ -0,0 +1,288 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use serde_json::{json, Value};
+use server::mail::mailer::{MailError, Mailer};
+
+use common::{bearer, client_with_stores, create_account, credentials, refresh, signin, stores, test_route_status, unique_username, PASSWORD};
+
+/// Keeps sent emails so the tests can read the verification codes, or fails to send them once `failing` is set
+#[derive(Default)]
+struct TestMailer {
+    sent: Mutex<Vec<(String, String)>>,
+    failing: AtomicBool
+}
+
+#[rocket::async_trait]
+impl Mailer for TestMailer {
+    async fn send(&self, to: &str, _subject: &str, body: &str) -> Result<(), MailError> {
+        if self.failing.load(Ordering::Relaxed) {
+            return Err(MailError("the mail server is down".to_string()));
+        }
+        self.sent.lock().unwrap().push((to.to_string(), body.to_string()));
+        Ok(())
+    }
+}
+
+impl TestMailer {
+    /// The verification code in the last email sent to an address
+    fn code_for(&self, to: &str) -> String {
+        let sent = self.sent.lock().unwrap();
+        let (_, body) = sent.iter().rev().find(|(address, _)| address == to).expect("email sent");
+        body.split_whitespace()
+            .find(|word| word.len() == 65 && word.ends_with('.'))
+            .map(|word| word.trim_end_matches('.').to_string())
+            .expect("verification code in email")
+    }
+}
+
+async fn client_with_mailer() -> (Client, Arc<TestMailer>) {
+    let mailer = Arc::new(TestMailer::default());
+    let rocket = server::build_rocket_with_mailer(stores().await, mailer.clone());
+
+    (Client::untracked(rocket).await.expect("valid rocket instance"), mailer)
+}
+
+async fn json_body(response: LocalResponse<'_>) -> Value {
+    serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+}
+
+async fn patch_me<'c>(client: &'c Client, token: &str, body: Value) -> LocalResponse<'c> {
+    client.patch("/me")
+        .header(ContentType::JSON)
+        .header(bearer(token))
+        .body(body.to_string())
+        .dispatch().await
+}
+
+#[rocket::async_test]
+async fn profile_shows_the_signed_in_user() {
+    let (client, _) = client_with_mailer().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.get("/me").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = json_body(response).await;
+    assert_eq!(body["username"], username);
+    assert!(body["display_name"].is_null());
+    assert!(body["email"].is_null());
+    assert_eq!(body["email_verified"], false);
+    assert!(body.get("password").is_none());
+
+    let response = client.get("/me").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn display_name_is_set_and_cleared() {
+    let (client, _) = client_with_mailer().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let body = json_body(patch_me(&client, &session.token, json!({ "display_name": "  Drew  " })).await).await;
+    assert_eq!(body["display_name"], "Drew");
+
+    // Fields left out are not changed
+    let body = json_body(patch_me(&client, &session.token, json!({})).await).await;
+    assert_eq!(body["display_name"], "Drew");
+
+    let body = json_body(patch_me(&client, &session.token, json!({ "display_name": null })).await).await;
+    assert!(body["display_name"].is_null());
+
+    let response = patch_me(&client, &session.token, json!({ "username": "someone-else" })).await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let response = patch_me(&client, &session.token, json!({ "display_name": "x".repeat(256) })).await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[rocket::async_test]
+async fn refused_updates_change_nothing() {
+    let (client, mailer) = client_with_mailer().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = patch_me(&client, &session.token, json!({ "display_name": "Drew", "email": "not-an-email" })).await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    // An address whose verification code cannot be emailed is not saved
+    mailer.failing.store(true, Ordering::Relaxed);
+    let response = patch_me(&client, &session.token, json!({ "display_name": "Drew", "email": format!("{}@example.com", username) })).await;
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let body = json_body(client.get("/me").header(bearer(&session.token)).dispatch().await).await;
+    assert!(body["display_name"].is_null());
+    assert!(body["email"].is_null());
+}
+
+#[rocket::async_test]
+async fn new_email_must_be_verified() {
+    let (client, mailer) = client_with_mailer().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    let email = format!("{}@example.com", username);
+
+    let response = patch_me(&client, &session.token, json!({ "email": "not-an-email" })).await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let body = json_body(patch_me(&client, &session.token, json!({ "email": email })).await).await;
+    assert_eq!(body["email"], email);
+    assert_eq!(body["email_verified"], false);
+
+    let response = client.post("/me/email/verify")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "token": "wrong" }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(json_body(response).await["error"]["code"], "invalid_email_token");
+
+    let response = client.post("/me/email/verify")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "token": mailer.code_for(&email) }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json_body(response).await["email_verified"], true);
+
+    // Setting the same address again keeps it verified, a new one needs verifying again
+    let body = json_body(patch_me(&client, &session.token, json!({ "email": email })).await).await;
+    assert_eq!(body["email_verified"], true);
+    let body = json_body(patch_me(&client, &session.token, json!({ "email": format!("new-{}", email) })).await).await;
+    assert_eq!(body["email_verified"], false);
+}
+
+#[rocket::async_test]
+async fn username_change_checks_uniqueness_and_reaches_the_next_token() {
+    let (client, _) = client_with_mailer().await;
+    let username = unique_username();
+    let taken = unique_username();
+    create_account(&client, &username).await;
+    create_account(&client, &taken).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.put("/me/username")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "username": taken }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let new_username = unique_username();
+    let response = client.put("/me/username")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "username": new_username }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json_body(response).await["username"], new_username);
+
+    let (status, refreshed) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    let response = client.get("/test").header(bearer(&refreshed.unwrap().token)).dispatch().await;
+    assert!(response.into_string().await.unwrap().contains(&new_username));
+
+    signin(&client, &new_username).await;
+}
+
+#[rocket::async_test]
+async fn delete_requires_the_password_and_removes_sessions() {
+    let (client, _) = client_with_mailer().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.delete("/me")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "password": "wrong password" }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client.delete("/me")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "password": PASSWORD }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn deleted_account_is_refused_and_purged_after_the_grace_period() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.delete("/me")
+        .header(ContentType::JSON)
+        .header(bearer(&session.token))
+        .body(json!({ "password": PASSWORD }).to_string())
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // The JWT is still within its lifetime but the account is gone
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Unauthorized);
+
+    // The username stays taken during the grace period
+    let response = client.post("/create")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    assert_eq!(stores.users.purge_deleted_users(chrono::Utc::now() - chrono::Duration::days(1)).await.unwrap(), 0);
+    assert!(stores.users.purge_deleted_users(chrono::Utc::now() + chrono::Duration::seconds(1)).await.unwrap() >= 1);
+
+    create_account(&client, &username).await;
+    signin(&client, &username).await;
+}
+
+#[rocket::async_test]
+async fn export_contains_the_account_roles_and_sessions() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let first = signin(&client, &username).await;
+    let session = signin(&client, &username).await;
+    refresh(&client, &first.token, &first.refresh_cookie).await;
+    server::cli::admin::add_role(&stores, &username, "support").await.unwrap();
+
+    let response = client.get("/me/export").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.headers().get_one("Content-Disposition").unwrap().starts_with("attachment"));
+    let body = json_body(response).await;
+
+    assert_eq!(body["account"]["username"], username);
+    assert!(body["account"].get("password").is_none());
+    assert_eq!(body["roles"], json!(["support"]));
+    assert_eq!(body["security_events"].as_array().unwrap().len(), 4);
+
+    // Both sign-ins, plus the rotated token and its replacement
+    let tokens = body["refresh_tokens"].as_array().unwrap();
+    assert_eq!(tokens.len(), 3);
+    assert!(tokens.iter().any(|token| !token["rotated_at"].is_null()));
+    assert!(tokens.iter().all(|token| token.get("token_hash").is_none()));
+
+    let response = client.get("/me/export").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use rocket::http::Status;
+use server::crypto::create_refresh::{RefreshConfig, SessionLifetime};
+use server::crypto::token_hash::hash_refresh_token;
+
+use uuid::Uuid;
+
+use common::{client, client_with_stores, create_account, refresh, signin, stores, test_route_status, unique_username, JWT_EXPIRE_TIME};
+
+#[rocket::async_test]
+async fn signin_expire_refresh_rotate() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let session = signin(&client, &username).await;
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Ok);
+
+    tokio::time::sleep(Duration::from_secs(JWT_EXPIRE_TIME + 1)).await;
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Unauthorized);
+
+    let (status, refreshed) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    let refreshed = refreshed.unwrap();
+    assert_ne!(refreshed.refresh_cookie.value(), session.refresh_cookie.value());
+    assert_eq!(test_route_status(&client, &refreshed.token).await, Status::Ok);
+
+    // The rotated token keeps working for the following refresh
+    let (status, rotated) = refresh(&client, &refreshed.token, &refreshed.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(test_route_status(&client, &rotated.unwrap().token).await, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn reused_refresh_token_revokes_session() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let session = signin(&client, &username).await;
+    let (status, refreshed) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    let refreshed = refreshed.unwrap();
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    // The whole session is revoked, including the token that replaced the reused one
+    let (status, _) = refresh(&client, &refreshed.token, &refreshed.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn reuse_only_revokes_its_own_session() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let first = signin(&client, &username).await;
+    let second = signin(&client, &username).await;
+
+    refresh(&client, &first.token, &first.refresh_cookie).await;
+    let (status, _) = refresh(&client, &first.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+
+    let (status, _) = refresh(&client, &second.token, &second.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn refresh_requires_cookie() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.post("/refresh")
+        .header(common::bearer(&session.token))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn refresh_rejects_token_of_another_user() {
+    let client = client().await;
+    let first_username = unique_username();
+    let second_username = unique_username();
+    create_account(&client, &first_username).await;
+    create_account(&client, &second_username).await;
+
+    let first = signin(&client, &first_username).await;
+    let second = signin(&client, &second_username).await;
+
+    let (status, _) = refresh(&client, &second.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn refresh_rejects_token_of_another_session() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let first = signin(&client, &username).await;
+    let second = signin(&client, &username).await;
+
+    let (status, _) = refresh(&client, &second.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = refresh(&client, &first.token, &first.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn expired_refresh_token_is_rejected() {
+    let stores = stores().await;
+    let client = client_with_stores(stores.clone()).await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    // Starting a second session for the user whose token has already expired
+    let user_id = stores.users.read_user(&username).await.unwrap()[0].user_id;
+    let expired_token = "expired-refresh-token";
+    stores.sessions.create_refresh_entry(user_id, Uuid::new_v4(), &hash_refresh_token(expired_token), Utc::now() - chrono::Duration::seconds(1))
+        .await.unwrap();
+    let expired_cookie = RefreshConfig::from_env().cookie(expired_token.to_string(), Utc::now());
+
+    let (status, _) = refresh(&client, &session.token, &expired_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[test]
+fn rotated_expiry_follows_session_mode() {
+    let mut config = RefreshConfig::from_env();
+    config.lifetime = chrono::Duration::days(1);
+    let signed_in = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+    let expiry = signed_in + config.lifetime;
+
+    config.mode = SessionLifetime::Sliding;
+    assert_eq!(config.rotated_expiry(expiry, now), now + config.lifetime);
+
+    config.mode = SessionLifetime::Absolute;
+    assert_eq!(config.rotated_expiry(expiry, now), expiry);
+}
+
+#[test]
+fn refresh_cookie_expires_with_the_token() {
+    let config = RefreshConfig::from_env();
+    let cookie = config.cookie("token".to_string(), Utc::now() + chrono::Duration::hours(1));
+
+    assert_eq!(cookie.name(), config.cookie_name);
+    assert!(cookie.http_only().unwrap());
+    let max_age = cookie.max_age().unwrap().whole_seconds();
+    assert!((3590..=3600).contains(&max_age));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use serde_json::Value;
+
+use common::{client, create_account, credentials, unique_username, JWT_EXPIRE_TIME, PASSWORD};
+
+#[rocket::async_test]
+async fn create_returns_user() {
+    let client = client().await;
+    let username = unique_username();
+
+    let response = client.post("/create")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["username"], username);
+    assert!(body["user_id"].is_i64());
+}
+
+#[rocket::async_test]
+async fn signin_returns_token_response() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().await.unwrap();
+    assert!(body["access_token"].is_string());
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["expires_in"], JWT_EXPIRE_TIME);
+    assert!(body["refresh_expires_in"].as_i64().unwrap() > 0);
+    assert_eq!(body["user"]["username"], username);
+}
+
+#[rocket::async_test]
+async fn errors_use_json_envelope() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let response = client.post("/create")
+        .header(ContentType::JSON)
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Conflict);
+    let correlation_id = response.headers().get_one("X-Correlation-Id").expect("correlation id header").to_string();
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "user_exists");
+    assert!(body["error"]["message"].is_string());
+    assert_eq!(body["error"]["correlation_id"], correlation_id);
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body(credentials(&username, "wrong password"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "incorrect_password");
+}
+
+#[rocket::async_test]
+async fn guard_failures_use_json_envelope() {
+    let client = client().await;
+
+    let response = client.get("/test")
+        .header(common::bearer("not-a-jwt"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .body("{\"username\": 1}")
+        .dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().await.unwrap();
+    assert_eq!(body["error"]["code"], "invalid_body");
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use serde_json::Value;
+use uuid::Uuid;
+
+use common::{client, client_credentials, create_account, refresh, signin, stores, test_route_status, unique_username, CLIENT_ID, CLIENT_SECRET, INTROSPECT_CLIENT_ID, INTROSPECT_CLIENT_SECRET};
+
+/// Revokes a token as the test client, returning the status
+async fn revoke(client: &Client, token: &str, hint: Option<&str>) -> Status {
+    let body = match hint {
+        Some(hint) => format!("token={}&token_type_hint={}", token, hint),
+        None => format!("token={}", token)
+    };
+
+    client.post("/revoke")
+        .header(ContentType::Form)
+        .header(client_credentials(CLIENT_ID, CLIENT_SECRET))
+        .body(body)
+        .dispatch().await
+        .status()
+}
+
+async fn is_active(client: &Client, token: &str) -> bool {
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .header(client_credentials(CLIENT_ID, CLIENT_SECRET))
+        .body(format!("token={}", token))
+        .dispatch().await;
+    let body: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+    body["active"].as_bool().unwrap()
+}
+
+#[rocket::async_test]
+async fn revocation_requires_client_credentials() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.post("/revoke")
+        .header(ContentType::Form)
+        .header(client_credentials(CLIENT_ID, "wrong secret"))
+        .body(format!("token={}", session.token))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn only_clients_allowed_to_revoke_can_revoke() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    let response = client.post("/revoke")
+        .header(ContentType::Form)
+        .header(client_credentials(INTROSPECT_CLIENT_ID, INTROSPECT_CLIENT_SECRET))
+        .body(format!("token={}", session.token))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Ok);
+
+    // The same client can still introspect
+    let response = client.post("/introspect")
+        .header(ContentType::Form)
+        .header(client_credentials(INTROSPECT_CLIENT_ID, INTROSPECT_CLIENT_SECRET))
+        .body(format!("token={}", session.token))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn revoked_access_tokens_are_refused() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+    let other = signin(&client, &username).await;
+
+    assert_eq!(revoke(&client, &session.token, Some("access_token")).await, Status::Ok);
+    assert_eq!(test_route_status(&client, &session.token).await, Status::Unauthorized);
+    assert!(!is_active(&client, &session.token).await);
+
+    // Only the token itself is revoked, not its session or the user's other tokens
+    assert_eq!(test_route_status(&client, &other.token).await, Status::Ok);
+    let (status, refreshed) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Ok);
+    assert_eq!(test_route_status(&client, &refreshed.unwrap().token).await, Status::Ok);
+
+    // Revoking again, or revoking something that is not a token, still succeeds
+    assert_eq!(revoke(&client, &session.token, None).await, Status::Ok);
+    assert_eq!(revoke(&client, "not-a-token", None).await, Status::Ok);
+}
+
+#[rocket::async_test]
+async fn revoked_refresh_tokens_end_their_session() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    let session = signin(&client, &username).await;
+
+    assert_eq!(revoke(&client, session.refresh_cookie.value(), Some("refresh_token")).await, Status::Ok);
+
+    let (status, _) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    assert_eq!(status, Status::Unauthorized);
+    assert!(!is_active(&client, session.refresh_cookie.value()).await);
+    assert!(!is_active(&client, &session.token).await);
+}
+
+#[rocket::async_test]
+async fn expired_denylist_entries_are_purged() {
+    let stores = stores().await;
+    let expired = Uuid::new_v4();
+    let current = Uuid::new_v4();
+    stores.sessions.revoke_access_token(expired, Utc::now() - Duration::minutes(1)).await.unwrap();
+    stores.sessions.revoke_access_token(current, Utc::now() + Duration::minutes(1)).await.unwrap();
+
+    assert!(stores.sessions.purge_revoked_access_tokens(Utc::now()).await.unwrap() >= 1);
+    assert!(!stores.sessions.is_access_token_revoked(expired).await.unwrap());
+    assert!(stores.sessions.is_access_token_revoked(current).await.unwrap());
+}
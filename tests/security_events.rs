@@ -0,0 +1,72 @@
+mod common;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use serde_json::Value;
+
+use common::{bearer, client, create_account, credentials, refresh, signin, unique_username};
+
+async fn security_events(client: &Client, token: &str, query: &str) -> Value {
+    let response = client.get(format!("/me/security-events{}", query)).header(bearer(token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+}
+
+fn event_types(page: &Value) -> Vec<&str> {
+    page["events"].as_array().unwrap().iter().map(|event| event["event_type"].as_str().unwrap()).collect()
+}
+
+#[rocket::async_test]
+async fn sign_ins_refreshes_and_logouts_are_recorded() {
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .header(Header::new("User-Agent", "audit-test-browser"))
+        .remote("203.0.113.7:5000".parse().unwrap())
+        .body(credentials(&username, "wrong password"))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let session = signin(&client, &username).await;
+    let (_, session) = refresh(&client, &session.token, &session.refresh_cookie).await;
+    let session = session.unwrap();
+    let response = client.post("/logout").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let session = signin(&client, &username).await;
+    let page = security_events(&client, &session.token, "").await;
+    assert_eq!(event_types(&page), vec!["signin_success", "logout", "refresh", "signin_success", "signin_failure", "signup"]);
+    assert_eq!(page["total"], 6);
+
+    let failure = &page["events"][4];
+    assert_eq!(failure["ip_address"], "203.0.113.7");
+    assert_eq!(failure["user_agent"], "audit-test-browser");
+    assert!(failure["created_at"].is_string());
+
+    let page = security_events(&client, &session.token, "?per_page=2&page=2").await;
+    assert_eq!(event_types(&page), vec!["refresh", "signin_success"]);
+}
+
+#[rocket::async_test]
+async fn users_only_see_their_own_events() {
+    let client = client().await;
+    let username = unique_username();
+    let other = unique_username();
+    create_account(&client, &username).await;
+    create_account(&client, &other).await;
+    signin(&client, &other).await;
+
+    let session = signin(&client, &username).await;
+    let page = security_events(&client, &session.token, "").await;
+    assert_eq!(event_types(&page), vec!["signin_success", "signup"]);
+
+    let response = client.get("/me/security-events").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client.get("/me/security-events?per_page=0").header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let response = client.get(format!("/me/security-events?page={}", i64::MAX)).header(bearer(&session.token)).dispatch().await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use rocket::http::{ContentType, Header, Status};
+use server::telemetry::otel::install_tracing;
+
+use common::{client, create_account, credentials, unique_username, PASSWORD};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+#[rocket::async_test]
+async fn sign_in_spans_join_the_callers_trace() {
+    // The subscriber is global, so this is the only test in this file
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    install_tracing(&provider).unwrap();
+
+    let client = client().await;
+    let username = unique_username();
+    create_account(&client, &username).await;
+    exporter.reset();
+
+    let response = client.post("/signin")
+        .header(ContentType::JSON)
+        .header(Header::new("traceparent", format!("00-{}-{}-01", TRACE_ID, PARENT_ID)))
+        .body(credentials(&username, PASSWORD))
+        .dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    provider.force_flush().unwrap();
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let request = spans.iter().find(|span| span.name == "POST /signin").expect("request span");
+    assert_eq!(request.span_context.trace_id(), TraceId::from_hex(TRACE_ID).unwrap());
+    assert_eq!(request.parent_span_id, SpanId::from_hex(PARENT_ID).unwrap());
+    assert!(request.attributes.iter().any(|attribute| attribute.key.as_str() == "http.response.status_code" && attribute.value.as_str() == "200"));
+
+    for name in ["PasswordHash::verify", "create_jwt"] {
+        let span = spans.iter().find(|span| span.name == name).unwrap_or_else(|| panic!("{} span", name));
+        assert_eq!(span.span_context.trace_id(), request.span_context.trace_id());
+        assert_eq!(span.parent_span_id, request.span_context.span_id());
+    }
+
+    // Requests without a traceparent start their own trace
+    exporter.reset();
+    client.get("/test").dispatch().await;
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let request = spans.iter().find(|span| span.name == "GET /test").expect("request span");
+    assert_ne!(request.span_context.trace_id(), TraceId::from_hex(TRACE_ID).unwrap());
+    assert_eq!(request.parent_span_id, SpanId::INVALID);
+}